@@ -0,0 +1,367 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Binary search tree, which stores all nodes in a single vector.
+//! Children are linked by indices into the vector instead of boxes, so
+//! nodes are allocated in bulk and stay close to each other in memory.
+
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+
+use crate::Tree;
+
+#[derive(Debug, Clone)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+/**
+ * Unbalanced binary search tree with nodes in an arena. Nodes are never
+ * freed one by one, the whole arena is released with the tree.
+ */
+#[derive(Debug, Clone)]
+pub struct ArenaTree<K, V> {
+    nodes: Vec<Node<K, V>>,
+    root: Option<usize>,
+}
+
+impl<K, V> Default for ArenaTree<K, V> {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            root: None,
+        }
+    }
+}
+
+impl<K, V> ArenaTree<K, V> {
+    /**
+     * Create an empty tree with the arena for at least `capacity` nodes,
+     * so that many inserts do not reallocate it.
+     */
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            nodes: Vec::with_capacity(capacity),
+            root: None,
+        }
+    }
+
+    /**
+     * Number of nodes, which the arena can hold without reallocation.
+     */
+    pub fn capacity(&self) -> usize {
+        self.nodes.capacity()
+    }
+
+    /**
+     * Grow the arena for at least `additional` more nodes.
+     */
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+    }
+
+    /**
+     * Iterate over references to keys and values in ascending key order.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        InOrder::new(&self.nodes, self.root)
+    }
+
+    /**
+     * Number of key-value pairs in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /**
+     * Remove all entries from the tree.
+     */
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = None;
+    }
+
+    /**
+     * Rewrite the arena, so nodes are stored in key order, and link them
+     * into a balanced tree. Neighbouring keys end up in neighbouring
+     * slots, which makes in-order traversals cache-friendly.
+     */
+    pub fn compact(&mut self) {
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut stack = Vec::new();
+        let mut subtree = self.root;
+        loop {
+            while let Some(index) = subtree {
+                stack.push(index);
+                subtree = self.nodes[index].left;
+            }
+            let Some(index) = stack.pop() else {
+                break;
+            };
+            order.push(index);
+            subtree = self.nodes[index].right;
+        }
+
+        let mut old_nodes: Vec<Option<Node<K, V>>> = core::mem::take(&mut self.nodes)
+            .into_iter()
+            .map(Some)
+            .collect();
+        self.nodes = order
+            .into_iter()
+            .filter_map(|index| old_nodes[index].take())
+            .collect();
+        let len = self.nodes.len();
+        self.root = Self::link_balanced(&mut self.nodes, 0, len);
+    }
+
+    /**
+     * Link nodes in slots `start..end`, which are sorted by key, into
+     * a balanced subtree with the median at the root.
+     */
+    fn link_balanced(nodes: &mut [Node<K, V>], start: usize, end: usize) -> Option<usize> {
+        if start == end {
+            return None;
+        }
+        let middle = start + (end - start) / 2;
+        nodes[middle].left = Self::link_balanced(nodes, start, middle);
+        nodes[middle].right = Self::link_balanced(nodes, middle + 1, end);
+        Some(middle)
+    }
+}
+
+impl<K, V> ArenaTree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Insert key-value pair into tree. When the key is already present,
+     * its value is replaced and the previous value is returned.
+     */
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let new_index = self.nodes.len();
+        let mut slot = &mut self.root;
+        while let Some(index) = *slot {
+            let node = &mut self.nodes[index];
+            slot = match key.cmp(&node.key) {
+                Ordering::Equal => return Some(core::mem::replace(&mut node.value, value)),
+                Ordering::Less => &mut node.left,
+                Ordering::Greater => &mut node.right,
+            };
+        }
+
+        *slot = Some(new_index);
+        self.nodes.push(Node {
+            key,
+            value,
+            left: None,
+            right: None,
+        });
+        None
+    }
+
+    /**
+     * Check is the key present in the tree.
+     */
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_index(key).is_some()
+    }
+
+    /**
+     * Get reference to the value stored under the key.
+     */
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_index(key).map(|index| &self.nodes[index].value)
+    }
+
+    /**
+     * Get mutable reference to the value stored under the key.
+     */
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let index = self.find_index(key)?;
+        Some(&mut self.nodes[index].value)
+    }
+
+    fn find_index<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut subtree = self.root;
+        while let Some(index) = subtree {
+            let node = &self.nodes[index];
+            subtree = match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return Some(index),
+                Ordering::Less => node.left,
+                Ordering::Greater => node.right,
+            };
+        }
+        None
+    }
+}
+
+impl<K, V> Tree<K, V>
+where
+    K: Ord,
+{
+    /**
+     * Create an empty tree, which allocates its nodes in an arena.
+     */
+    pub fn with_arena() -> ArenaTree<K, V> {
+        ArenaTree::new()
+    }
+
+    /**
+     * Create an empty tree with an arena for at least `capacity` nodes.
+     */
+    pub fn with_arena_capacity(capacity: usize) -> ArenaTree<K, V> {
+        ArenaTree::with_capacity(capacity)
+    }
+}
+
+/**
+ * In-order iterator over the arena, with the left spine of not yet
+ * visited subtrees on the stack.
+ */
+struct InOrder<'a, K, V> {
+    nodes: &'a [Node<K, V>],
+    stack: Vec<usize>,
+}
+
+impl<'a, K, V> InOrder<'a, K, V> {
+    fn new(nodes: &'a [Node<K, V>], root: Option<usize>) -> Self {
+        let mut iter = Self {
+            nodes,
+            stack: Vec::new(),
+        };
+        iter.push_left_spine(root);
+        iter
+    }
+
+    fn push_left_spine(&mut self, mut subtree: Option<usize>) {
+        while let Some(index) = subtree {
+            self.stack.push(index);
+            subtree = self.nodes[index].left;
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for InOrder<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = &self.nodes[self.stack.pop()?];
+        self.push_left_spine(node.right);
+        Some((&node.key, &node.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Tree;
+
+    #[test]
+    fn same_entries_as_boxed_tree() {
+        let mut arena = Tree::with_arena();
+        let mut boxed = Tree::new();
+
+        // Multiplication by a number coprime with the modulus permutes keys.
+        for i in 0..1_000_000u64 {
+            let key = i * 7_919 % 1_000_003;
+            assert_eq!(arena.insert(key, i), boxed.insert(key, i));
+        }
+        assert_eq!(arena.insert(0, 1), boxed.insert(0, 1));
+
+        assert_eq!(arena.len(), boxed.len());
+        assert!(arena.iter().eq(boxed.iter()));
+        assert_eq!(arena.get(&7_919), Some(&1));
+        assert_eq!(arena.get(&1_000_002), boxed.get(&1_000_002));
+        assert!(!arena.contains(&1_000_003));
+    }
+
+    #[test]
+    fn preallocated_arena_is_not_reallocated() {
+        let mut tree = Tree::with_arena_capacity(10_000);
+        let capacity = tree.capacity();
+        let buffer = tree.nodes.as_ptr();
+        assert!(capacity >= 10_000);
+
+        for i in 0..10_000u64 {
+            tree.insert(i * 7_919 % 10_007, i);
+        }
+        assert_eq!(tree.len(), 10_000);
+        assert_eq!(tree.capacity(), capacity);
+        assert_eq!(tree.nodes.as_ptr(), buffer);
+
+        tree.reserve(5_000);
+        assert!(tree.capacity() >= 15_000);
+    }
+
+    #[test]
+    fn compact_stores_nodes_in_key_order() {
+        let mut tree = Tree::with_arena();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i * 10);
+        }
+
+        tree.compact();
+        let keys: Vec<i32> = tree.nodes.iter().map(|node| node.key).collect();
+        assert_eq!(keys, [1, 3, 5, 6, 11, 42, 65, 123]);
+        assert!(tree
+            .iter()
+            .map(|(k, v)| (*k, *v))
+            .eq(keys.iter().map(|k| (*k, k * 10))));
+        assert_eq!(tree.root, Some(4));
+        assert_eq!(tree.get(&6), Some(&60));
+
+        tree.insert(7, 70);
+        assert_eq!(tree.get(&7), Some(&70));
+    }
+
+    #[test]
+    fn get_mut_and_clear() {
+        let mut tree = Tree::with_arena();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i);
+        }
+        assert_eq!(tree.len(), 8);
+
+        *tree.get_mut(&42).unwrap() += 1;
+        assert_eq!(tree.get(&42), Some(&43));
+        assert!(tree
+            .iter()
+            .map(|(k, _)| *k)
+            .eq([1, 3, 5, 6, 11, 42, 65, 123]));
+
+        tree.clear();
+        assert!(tree.is_empty());
+        assert_eq!(tree.iter().next(), None);
+    }
+}
@@ -0,0 +1,376 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Self-balancing AVL tree. Each node stores the height of its subtree,
+//! and rotations after every insert and remove keep heights of sibling
+//! subtrees different by one at most, so the tree height stays O(log n).
+
+use alloc::boxed::Box;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+
+use crate::binary_node::{self, BinaryNode, InOrder, OwnedNode};
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+#[derive(Debug)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+
+    left: Link<K, V>,
+    right: Link<K, V>,
+
+    // Number of nodes on the longest path from this node to a leaf.
+    height: usize,
+}
+
+impl<K, V> Node<K, V> {
+    fn new(key: K, value: V) -> Self {
+        Self {
+            key,
+            value,
+            left: None,
+            right: None,
+            height: 1,
+        }
+    }
+
+    fn height(link: &Link<K, V>) -> usize {
+        link.as_ref().map_or(0, |node| node.height)
+    }
+
+    fn update_height(&mut self) {
+        self.height = 1 + Self::height(&self.left).max(Self::height(&self.right));
+    }
+
+    /**
+     * Height of the left subtree minus height of the right subtree.
+     */
+    fn balance_factor(&self) -> isize {
+        Self::height(&self.left) as isize - Self::height(&self.right) as isize
+    }
+}
+
+impl<K, V> BinaryNode for Node<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn key(&self) -> &K {
+        &self.key
+    }
+
+    fn value(&self) -> &V {
+        &self.value
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+impl<K, V> OwnedNode for Node<K, V> {
+    fn value_mut(&mut self) -> &mut V {
+        &mut self.value
+    }
+
+    fn left_link(&mut self) -> &mut Link<K, V> {
+        &mut self.left
+    }
+
+    fn right_link(&mut self) -> &mut Link<K, V> {
+        &mut self.right
+    }
+}
+
+/**
+ * Balanced binary search tree with the same API as `Tree`.
+ */
+#[derive(Debug)]
+pub struct AvlTree<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+impl<K, V> Default for AvlTree<K, V> {
+    fn default() -> Self {
+        Self { root: None, len: 0 }
+    }
+}
+
+impl<K, V> AvlTree<K, V> {
+    /**
+     * Iterate over references to keys and values in ascending key order.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        InOrder::new(self.root.as_deref())
+    }
+
+    /**
+     * Number of key-value pairs in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Number of nodes on the longest path from the root to a leaf.
+     */
+    pub fn height(&self) -> usize {
+        Node::height(&self.root)
+    }
+}
+
+impl<K, V> AvlTree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Insert key-value pair into tree. When the key is already present,
+     * its value is replaced and the previous value is returned.
+     */
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let previous = Self::insert_at(&mut self.root, key, value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    /**
+     * Insert key-value pair into tree, only when the key is not present.
+     * Returns false and keeps the existing value otherwise.
+     */
+    pub fn try_insert(&mut self, key: K, value: V) -> bool {
+        if self.contains(&key) {
+            return false;
+        }
+        self.insert(key, value);
+        true
+    }
+
+    fn insert_at(slot: &mut Link<K, V>, key: K, value: V) -> Option<V> {
+        let Some(current_node) = slot else {
+            *slot = Some(Box::new(Node::new(key, value)));
+            return None;
+        };
+
+        let previous = match key.cmp(&current_node.key) {
+            Ordering::Equal => return Some(core::mem::replace(&mut current_node.value, value)),
+            Ordering::Less => Self::insert_at(&mut current_node.left, key, value),
+            Ordering::Greater => Self::insert_at(&mut current_node.right, key, value),
+        };
+
+        if previous.is_none() {
+            Self::rebalance(slot);
+        }
+        previous
+    }
+
+    /**
+     * Check is the key present in the tree.
+     */
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /**
+     * Get reference to the value stored under the key.
+     */
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        binary_node::find(self.root.as_deref(), key).map(BinaryNode::value)
+    }
+
+    /**
+     * Get mutable reference to the value stored under the key.
+     */
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        binary_node::find_mut(self.root.as_deref_mut(), key).map(OwnedNode::value_mut)
+    }
+
+    /**
+     * Remove node with the key from tree and return its value.
+     */
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let removed = Self::remove_at(&mut self.root, key);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    fn remove_at<Q>(slot: &mut Link<K, V>, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let current_node = slot.as_deref_mut()?;
+
+        let removed = match key.cmp(current_node.key.borrow()) {
+            Ordering::Less => Self::remove_at(&mut current_node.left, key),
+            Ordering::Greater => Self::remove_at(&mut current_node.right, key),
+            Ordering::Equal => {
+                let mut removed_node = slot.take()?;
+                *slot = match (removed_node.left.take(), removed_node.right.take()) {
+                    (None, child) | (child, None) => child,
+
+                    (Some(left), Some(right)) => {
+                        let mut right = Some(right);
+                        let mut successor = Self::remove_min(&mut right)?;
+                        successor.left = Some(left);
+                        successor.right = right;
+                        Some(successor)
+                    }
+                };
+                Some(removed_node.value)
+            }
+        };
+
+        if removed.is_some() {
+            Self::rebalance(slot);
+        }
+        removed
+    }
+
+    /**
+     * Detach the leftmost node of the subtree and rebalance the path to it.
+     */
+    fn remove_min(slot: &mut Link<K, V>) -> Option<Box<Node<K, V>>> {
+        let current_node = slot.as_deref_mut()?;
+
+        if current_node.left.is_some() {
+            let min_node = Self::remove_min(&mut current_node.left);
+            Self::rebalance(slot);
+            return min_node;
+        }
+
+        let mut min_node = slot.take()?;
+        *slot = min_node.right.take();
+        Some(min_node)
+    }
+
+    /**
+     * Restore the AVL property of the subtree in the slot, assuming that
+     * both its children are valid AVL trees with heights, which differ
+     * by two at most.
+     */
+    fn rebalance(slot: &mut Link<K, V>) {
+        let Some(mut node) = slot.take() else {
+            return;
+        };
+        node.update_height();
+
+        let balance_factor = node.balance_factor();
+        if balance_factor > 1 {
+            if node
+                .left
+                .as_ref()
+                .is_some_and(|left| left.balance_factor() < 0)
+            {
+                node.left = node.left.take().map(Self::rotate_left);
+            }
+            node = Self::rotate_right(node);
+        } else if balance_factor < -1 {
+            if node
+                .right
+                .as_ref()
+                .is_some_and(|right| right.balance_factor() > 0)
+            {
+                node.right = node.right.take().map(Self::rotate_right);
+            }
+            node = Self::rotate_left(node);
+        }
+        *slot = Some(node);
+    }
+
+    /**
+     * Move the right child of the node up, the node becomes its left child.
+     */
+    fn rotate_left(mut node: Box<Node<K, V>>) -> Box<Node<K, V>> {
+        let Some(mut pivot) = node.right.take() else {
+            return node;
+        };
+        node.right = pivot.left.take();
+        node.update_height();
+        pivot.left = Some(node);
+        pivot.update_height();
+        pivot
+    }
+
+    /**
+     * Move the left child of the node up, the node becomes its right child.
+     */
+    fn rotate_right(mut node: Box<Node<K, V>>) -> Box<Node<K, V>> {
+        let Some(mut pivot) = node.left.take() else {
+            return node;
+        };
+        node.left = pivot.right.take();
+        node.update_height();
+        pivot.right = Some(node);
+        pivot.update_height();
+        pivot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Check heights and the AVL property of every node.
+     */
+    fn assert_avl<K, V>(tree: &AvlTree<K, V>) {
+        let mut stack: Vec<&Node<K, V>> = tree.root.as_deref().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let children_height = Node::height(&node.left).max(Node::height(&node.right));
+            assert_eq!(node.height, 1 + children_height);
+            assert!(node.balance_factor().abs() <= 1);
+            stack.extend(node.left.as_deref());
+            stack.extend(node.right.as_deref());
+        }
+    }
+
+    #[test]
+    fn updates_keep_avl_property() {
+        let mut tree = AvlTree::new();
+        for i in 0..1_000 {
+            tree.insert(i, i);
+        }
+        assert_avl(&tree);
+
+        for i in (0..1_000).filter(|i| i % 3 != 0) {
+            tree.remove(&i);
+        }
+        assert_avl(&tree);
+    }
+}
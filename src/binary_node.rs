@@ -0,0 +1,273 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Traversals and lookups shared by all tree variants.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+
+/**
+ * Read-only view of a node, which is common for nodes of all tree variants.
+ */
+pub(crate) trait BinaryNode {
+    type Key;
+    type Value;
+
+    fn key(&self) -> &Self::Key;
+    fn value(&self) -> &Self::Value;
+    fn left(&self) -> Option<&Self>;
+    fn right(&self) -> Option<&Self>;
+}
+
+/**
+ * Node, which owns its children in boxes, so lookups can return mutable
+ * references and subtrees can be relinked.
+ */
+pub(crate) trait OwnedNode: BinaryNode + Sized {
+    fn value_mut(&mut self) -> &mut Self::Value;
+    fn left_link(&mut self) -> &mut Option<Box<Self>>;
+    fn right_link(&mut self) -> &mut Option<Box<Self>>;
+
+    /**
+     * Called by `rebuild` for every relinked node with the number of nodes
+     * in its new subtree, so the node can update its balancing data.
+     */
+    fn relinked(&mut self, _size: usize) {}
+}
+
+/**
+ * Node with the key in the subtree.
+ */
+pub(crate) fn find<'a, N, Q>(mut subtree: Option<&'a N>, key: &Q) -> Option<&'a N>
+where
+    N: BinaryNode,
+    N::Key: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    while let Some(node) = subtree {
+        subtree = match key.cmp(node.key().borrow()) {
+            Ordering::Equal => return Some(node),
+            Ordering::Less => node.left(),
+            Ordering::Greater => node.right(),
+        };
+    }
+    None
+}
+
+/**
+ * Node with the key in the subtree, like `find`, for modification.
+ */
+pub(crate) fn find_mut<'a, N, Q>(mut subtree: Option<&'a mut N>, key: &Q) -> Option<&'a mut N>
+where
+    N: OwnedNode,
+    N::Key: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    while let Some(node) = subtree {
+        subtree = match key.cmp(node.key().borrow()) {
+            Ordering::Equal => return Some(node),
+            Ordering::Less => node.left_link().as_deref_mut(),
+            Ordering::Greater => node.right_link().as_deref_mut(),
+        };
+    }
+    None
+}
+
+/**
+ * Number of nodes on the longest path from the root of the subtree
+ * to a leaf, counted in a loop, so deep chains do not overflow the stack.
+ */
+pub(crate) fn height<N: BinaryNode>(root: Option<&N>) -> usize {
+    let mut height = 0;
+    let mut stack: Vec<(&N, usize)> = root.map(|node| (node, 1)).into_iter().collect();
+    while let Some((node, depth)) = stack.pop() {
+        height = height.max(depth);
+        stack.extend(node.left().map(|left| (left, depth + 1)));
+        stack.extend(node.right().map(|right| (right, depth + 1)));
+    }
+    height
+}
+
+/**
+ * Rebuild the subtree in the slot into a perfectly balanced one,
+ * reusing its nodes.
+ */
+pub(crate) fn rebuild<N: OwnedNode>(slot: &mut Option<Box<N>>) {
+    let mut nodes = Vec::new();
+    let mut stack = Vec::new();
+    let mut subtree = slot.take();
+    loop {
+        while let Some(mut node) = subtree {
+            subtree = node.left_link().take();
+            stack.push(node);
+        }
+        let Some(mut node) = stack.pop() else {
+            break;
+        };
+        subtree = node.right_link().take();
+        nodes.push(node);
+    }
+
+    let len = nodes.len();
+    *slot = build_balanced(&mut nodes.into_iter(), len);
+}
+
+/**
+ * Link the next `len` nodes of the sorted sequence into a balanced
+ * subtree with the median at the root.
+ */
+fn build_balanced<N, I>(nodes: &mut I, len: usize) -> Option<Box<N>>
+where
+    N: OwnedNode,
+    I: Iterator<Item = Box<N>>,
+{
+    if len == 0 {
+        return None;
+    }
+
+    let left_len = len / 2;
+    let left = build_balanced(nodes, left_len);
+    let mut node = nodes.next()?;
+    *node.left_link() = left;
+    *node.right_link() = build_balanced(nodes, len - left_len - 1);
+    node.relinked(len);
+    Some(node)
+}
+
+/**
+ * In-order iterator over nodes of any tree variant. The stack holds
+ * the left spine of not yet visited subtrees.
+ */
+pub(crate) struct InOrder<'a, N> {
+    stack: Vec<&'a N>,
+}
+
+impl<'a, N: BinaryNode> InOrder<'a, N> {
+    pub(crate) fn new(root: Option<&'a N>) -> Self {
+        let mut iter = Self { stack: Vec::new() };
+        iter.push_left_spine(root);
+        iter
+    }
+
+    fn push_left_spine(&mut self, mut subtree: Option<&'a N>) {
+        while let Some(node) = subtree {
+            self.stack.push(node);
+            subtree = node.left();
+        }
+    }
+}
+
+impl<'a, N: BinaryNode> Iterator for InOrder<'a, N> {
+    type Item = (&'a N::Key, &'a N::Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left_spine(node.right());
+        Some((node.key(), node.value()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AvlTree, ScapegoatTree, SplayTree, Treap, WeightBalancedTree};
+
+    /**
+     * Run the same block for each tree, bound to the pattern.
+     */
+    macro_rules! for_each_tree {
+        (for $pattern:pat in [$($tree:expr),* $(,)?] $body:block) => {
+            $({
+                let $pattern = $tree;
+                $body
+            })*
+        };
+    }
+
+    #[test]
+    fn same_api_as_tree() {
+        for_each_tree!(for mut tree in [
+            AvlTree::new(),
+            Treap::new(),
+            SplayTree::new(),
+            ScapegoatTree::new(),
+            WeightBalancedTree::new(),
+        ] {
+            for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+                tree.insert(i, i);
+            }
+
+            assert_eq!(tree.len(), 8);
+            assert_eq!(tree.insert(3, 30), Some(3));
+            assert!(tree.contains(&42));
+            *tree.get_mut(&42).unwrap() += 1;
+            assert_eq!(tree.get_mut(&42).copied(), Some(43));
+            assert_eq!(tree.get_mut(&7), None);
+            assert_eq!(tree.remove(&5), Some(5));
+            assert_eq!(tree.remove(&5), None);
+            assert!(!tree.contains(&5));
+            assert!(tree.iter().map(|(k, _)| *k).eq([1, 3, 6, 11, 42, 65, 123]));
+            assert!(!tree.is_empty());
+        });
+    }
+
+    #[test]
+    fn try_insert_keeps_existing_value() {
+        for_each_tree!(for mut tree in [
+            AvlTree::new(),
+            Treap::new(),
+            SplayTree::new(),
+            ScapegoatTree::new(),
+        ] {
+            tree.insert(5, 5);
+            assert!(!tree.try_insert(5, 0));
+            assert!(tree.try_insert(6, 6));
+            assert_eq!(tree.get_mut(&5).copied(), Some(5));
+            assert_eq!(tree.len(), 2);
+        });
+    }
+
+    #[test]
+    fn sorted_insert_keeps_height_logarithmic() {
+        // log2(10_000) is about 13.3, and log(10_000) with base 1 / 0.7
+        // is about 25.8.
+        for_each_tree!(for (mut tree, max_height) in [
+            (AvlTree::new(), 29),
+            (Treap::with_seed(42), 3 * 14),
+            (ScapegoatTree::new(), 27),
+            (WeightBalancedTree::new(), 26),
+        ] {
+            for i in 0..10_000 {
+                assert_eq!(tree.insert(i, i), None);
+            }
+
+            assert_eq!(tree.len(), 10_000);
+            assert!(tree.height() <= max_height, "height is {}", tree.height());
+            assert!(tree.iter().map(|(k, _)| *k).eq(0..10_000));
+            assert_eq!(tree.get(&9_999), Some(&9_999));
+        });
+    }
+
+    #[test]
+    fn remove_keeps_height_logarithmic() {
+        for_each_tree!(for (mut tree, max_height) in [
+            (AvlTree::new(), 12),
+            (Treap::new(), 3 * 9),
+            (ScapegoatTree::new(), 18),
+            (WeightBalancedTree::new(), 18),
+        ] {
+            for i in 0..1_000 {
+                tree.insert(i, i * 2);
+            }
+
+            for i in (0..1_000).filter(|i| i % 3 != 0) {
+                assert_eq!(tree.remove(&i), Some(i * 2));
+            }
+            assert_eq!(tree.remove(&1), None);
+
+            assert_eq!(tree.len(), 334);
+            assert!(tree.iter().map(|(k, _)| *k).eq((0..1_000).step_by(3)));
+            assert!(tree.height() <= max_height, "height is {}", tree.height());
+        });
+    }
+}
@@ -0,0 +1,469 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Binary search tree, which orders keys by a comparison function given
+//! at construction instead of the natural order of keys, like `sort_by`.
+//! `DescendingTree` is such a tree with the reversed natural order.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::binary_node::{BinaryNode, InOrder};
+use crate::Tree;
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl<K, V> BinaryNode for Node<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn key(&self) -> &K {
+        &self.key
+    }
+
+    fn value(&self) -> &V {
+        &self.value
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+/**
+ * Unbalanced binary search tree ordered by the comparator. Keys, which
+ * the comparator reports as equal, are the same key for the tree.
+ */
+pub struct ComparatorTree<K, V, F> {
+    root: Link<K, V>,
+    len: usize,
+    cmp: F,
+}
+
+impl<K, V, F> Drop for ComparatorTree<K, V, F> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<K, V, F> ComparatorTree<K, V, F> {
+    /**
+     * Iterate over references to keys and values in the comparator order.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        InOrder::new(self.root.as_deref())
+    }
+
+    /**
+     * Number of key-value pairs in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Entry, which comes first in the comparator order.
+     */
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = node.left.as_deref() {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /**
+     * Entry, which comes last in the comparator order.
+     */
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = node.right.as_deref() {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /**
+     * Remove all entries from the tree, the comparator is kept.
+     */
+    pub fn clear(&mut self) {
+        let mut stack: Vec<Box<Node<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+        self.len = 0;
+    }
+}
+
+impl<K, V, F> ComparatorTree<K, V, F>
+where
+    F: Fn(&K, &K) -> Ordering,
+{
+    pub fn new(cmp: F) -> Self {
+        Self {
+            root: None,
+            len: 0,
+            cmp,
+        }
+    }
+
+    /**
+     * Insert key-value pair into tree. When the key is already present,
+     * its value is replaced and the previous value is returned.
+     */
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut slot = &mut self.root;
+        while let Some(node) = slot {
+            slot = match (self.cmp)(&key, &node.key) {
+                Ordering::Equal => return Some(core::mem::replace(&mut node.value, value)),
+                Ordering::Less => &mut node.left,
+                Ordering::Greater => &mut node.right,
+            };
+        }
+
+        *slot = Some(Box::new(Node {
+            key,
+            value,
+            left: None,
+            right: None,
+        }));
+        self.len += 1;
+        None
+    }
+
+    /**
+     * Check is the key present in the tree.
+     */
+    pub fn contains(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /**
+     * Get reference to the value stored under the key.
+     */
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut subtree = self.root.as_deref();
+        while let Some(node) = subtree {
+            subtree = match (self.cmp)(key, &node.key) {
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+            };
+        }
+        None
+    }
+
+    /**
+     * Get mutable reference to the value stored under the key.
+     */
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut subtree = self.root.as_deref_mut();
+        while let Some(node) = subtree {
+            subtree = match (self.cmp)(key, &node.key) {
+                Ordering::Equal => return Some(&mut node.value),
+                Ordering::Less => node.left.as_deref_mut(),
+                Ordering::Greater => node.right.as_deref_mut(),
+            };
+        }
+        None
+    }
+
+    /**
+     * Remove node with the key from tree and return its value.
+     * A node with two children is replaced by its in-order successor.
+     */
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let mut slot = &mut self.root;
+        loop {
+            match (self.cmp)(key, &slot.as_ref()?.key) {
+                Ordering::Equal => break,
+                Ordering::Less => slot = &mut slot.as_mut()?.left,
+                Ordering::Greater => slot = &mut slot.as_mut()?.right,
+            }
+        }
+
+        let mut removed_node = slot.take()?;
+        *slot = match (removed_node.left.take(), removed_node.right.take()) {
+            (None, child) | (child, None) => child,
+
+            (Some(left), Some(right)) => {
+                let mut right = Some(right);
+                let mut successor = Self::remove_min(&mut right)?;
+                successor.left = Some(left);
+                successor.right = right;
+                Some(successor)
+            }
+        };
+        self.len -= 1;
+        Some(removed_node.value)
+    }
+
+    /**
+     * Detach the leftmost node of the subtree in the slot.
+     */
+    fn remove_min(mut slot: &mut Link<K, V>) -> Option<Box<Node<K, V>>> {
+        while slot.as_ref()?.left.is_some() {
+            slot = &mut slot.as_mut()?.left;
+        }
+        let mut min_node = slot.take()?;
+        *slot = min_node.right.take();
+        Some(min_node)
+    }
+}
+
+impl<K, V, F> fmt::Debug for ComparatorTree<K, V, F>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/**
+ * Tree, which iterates keys in descending order, so the largest key
+ * is the first one.
+ */
+pub struct DescendingTree<K, V> {
+    tree: ComparatorTree<K, V, fn(&K, &K) -> Ordering>,
+}
+
+impl<K, V> DescendingTree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self {
+            tree: ComparatorTree::new(|a, b| b.cmp(a)),
+        }
+    }
+
+    /**
+     * Iterate over references to keys and values in descending key order.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.tree.iter()
+    }
+
+    /**
+     * Number of key-value pairs in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /**
+     * Entry with the smallest key, which is the last one in the tree.
+     */
+    pub fn min(&self) -> Option<(&K, &V)> {
+        self.tree.last()
+    }
+
+    /**
+     * Entry with the largest key, which is the first one in the tree.
+     */
+    pub fn max(&self) -> Option<(&K, &V)> {
+        self.tree.first()
+    }
+
+    /**
+     * Insert key-value pair into tree. When the key is already present,
+     * its value is replaced and the previous value is returned.
+     */
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.tree.insert(key, value)
+    }
+
+    /**
+     * Check is the key present in the tree.
+     */
+    pub fn contains(&self, key: &K) -> bool {
+        self.tree.contains(key)
+    }
+
+    /**
+     * Get reference to the value stored under the key.
+     */
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.tree.get(key)
+    }
+
+    /**
+     * Get mutable reference to the value stored under the key.
+     */
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.tree.get_mut(key)
+    }
+
+    /**
+     * Remove node with the key from tree and return its value.
+     */
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.tree.remove(key)
+    }
+
+    /**
+     * Remove all entries from the tree.
+     */
+    pub fn clear(&mut self) {
+        self.tree.clear();
+    }
+}
+
+impl<K, V> Default for DescendingTree<K, V>
+where
+    K: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> fmt::Debug for DescendingTree<K, V>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tree.fmt(f)
+    }
+}
+
+impl<K, V> Tree<K, V>
+where
+    K: Ord,
+{
+    /**
+     * Create an empty tree, which iterates keys in descending order.
+     */
+    pub fn new_descending() -> DescendingTree<K, V> {
+        DescendingTree::new()
+    }
+}
+
+impl<K, V> Tree<K, V> {
+    /**
+     * Create an empty tree, which orders keys by the comparator instead
+     * of their natural order, so keys are not required to be `Ord`.
+     */
+    pub fn with_comparator<F>(cmp: F) -> ComparatorTree<K, V, F>
+    where
+        F: Fn(&K, &K) -> Ordering,
+    {
+        ComparatorTree::new(cmp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Tree;
+
+    #[test]
+    fn descending_comparator() {
+        let mut tree = Tree::with_comparator(|a: &i32, b: &i32| b.cmp(a));
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i);
+        }
+
+        assert_eq!(tree.len(), 8);
+        assert!(tree
+            .iter()
+            .map(|(k, _)| *k)
+            .eq([123, 65, 42, 11, 6, 5, 3, 1]));
+        assert_eq!(tree.insert(6, 60), Some(6));
+        assert_eq!(tree.get(&6), Some(&60));
+        assert!(!tree.contains(&7));
+    }
+
+    #[test]
+    fn order_by_derived_field() {
+        let mut tree = Tree::with_comparator(|a: &(&str, u32), b: &(&str, u32)| a.1.cmp(&b.1));
+        tree.insert(("carol", 30), ());
+        tree.insert(("alice", 10), ());
+        tree.insert(("bob", 20), ());
+        // Same age is the same key for the comparator.
+        assert_eq!(tree.insert(("dave", 20), ()), Some(()));
+
+        assert!(tree.iter().map(|(k, _)| k.0).eq(["alice", "bob", "carol"]));
+        assert_eq!(tree.remove(&("anyone", 10)), Some(()));
+        assert_eq!(tree.len(), 2);
+    }
+
+    #[test]
+    fn remove_with_comparator() {
+        let mut tree = Tree::with_comparator(|a: &i32, b: &i32| b.cmp(a));
+        for i in [5, 3, 65, 123, 6, 11, 1, 42] {
+            tree.insert(i, i);
+        }
+
+        assert_eq!(tree.remove(&5), Some(5));
+        assert_eq!(tree.remove(&65), Some(65));
+        assert_eq!(tree.remove(&65), None);
+        assert_eq!(tree.len(), 6);
+        assert!(tree.iter().map(|(k, _)| *k).eq([123, 42, 11, 6, 3, 1]));
+
+        *tree.get_mut(&42).unwrap() += 1;
+        assert_eq!(tree.get(&42), Some(&43));
+        tree.clear();
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn descending_tree_keeps_true_min_and_max() {
+        let mut tree = Tree::new_descending();
+        for i in [5, 3, 6] {
+            tree.insert(i, i * 10);
+        }
+
+        assert!(tree.iter().map(|(k, _)| *k).eq([6, 5, 3]));
+        assert_eq!(tree.min(), Some((&3, &30)));
+        assert_eq!(tree.max(), Some((&6, &60)));
+        assert_eq!(tree.remove(&6), Some(60));
+        assert_eq!(tree.max(), Some((&5, &50)));
+        assert_eq!(tree.len(), 2);
+    }
+
+    #[test]
+    fn drop_deep_chain_without_stack_overflow() {
+        let thread = std::thread::Builder::new().stack_size(64 * 1024);
+        let handle = thread
+            .spawn(|| {
+                let mut tree = Tree::new_descending();
+                for i in (0..10_000).rev() {
+                    tree.insert(i, i);
+                }
+                assert_eq!(tree.len(), 10_000);
+            })
+            .unwrap();
+        handle.join().unwrap();
+    }
+}
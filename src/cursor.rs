@@ -0,0 +1,125 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Cursor, which moves over entries of the tree in both directions.
+
+use crate::{Node, Tree};
+
+/**
+ * Position in the tree: at an entry, or at the "ghost" position, which
+ * is before the first entry and after the last one. Moves step from
+ * a node to its neighbour through parent pointers.
+ */
+#[derive(Debug, Clone)]
+pub struct Cursor<'a, K, V> {
+    tree: &'a Tree<K, V>,
+    current: Option<&'a Node<K, V>>,
+}
+
+impl<K, V> Tree<K, V> {
+    /**
+     * Cursor at the ghost position, so `move_next` goes to the first
+     * entry and `move_prev` goes to the last one.
+     */
+    pub fn cursor(&self) -> Cursor<'_, K, V> {
+        Cursor {
+            tree: self,
+            current: None,
+        }
+    }
+}
+
+impl<'a, K, V> Cursor<'a, K, V> {
+    /**
+     * Entry at the cursor, or None at the ghost position.
+     */
+    pub fn current(&self) -> Option<(&'a K, &'a V)> {
+        self.current.map(|node| (&node.key, &node.value))
+    }
+
+    /**
+     * Move to the next entry in ascending key order and return it.
+     * From the last entry the cursor moves to the ghost position.
+     */
+    pub fn move_next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.current = match self.current {
+            Some(node) => node.next(),
+            None => self.tree.root.as_deref().map(Node::leftmost),
+        };
+        self.current()
+    }
+
+    /**
+     * Move to the previous entry and return it, mirror of `move_next`.
+     */
+    pub fn move_prev(&mut self) -> Option<(&'a K, &'a V)> {
+        self.current = match self.current {
+            Some(node) => node.prev(),
+            None => self.tree.root.as_deref().map(Node::rightmost),
+        };
+        self.current()
+    }
+}
+
+impl<'a, K, V> Cursor<'a, K, V>
+where
+    K: Ord,
+{
+    /**
+     * Move to the entry with the smallest key, which is greater than
+     * or equal to the key, like `lower_bound`, and return it. When there
+     * is no such entry, the cursor moves to the ghost position.
+     */
+    pub fn seek(&mut self, key: &K) -> Option<(&'a K, &'a V)> {
+        self.current = self.tree.first_node_after(key, true);
+        self.current()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Tree;
+
+    fn demo_tree() -> Tree<i32, i32> {
+        let mut tree = Tree::new();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i);
+        }
+        tree
+    }
+
+    #[test]
+    fn seek_and_move_both_ways() {
+        let tree = demo_tree();
+        let mut cursor = tree.cursor();
+
+        assert_eq!(cursor.seek(&6), Some((&6, &6)));
+        assert_eq!(cursor.move_next(), Some((&11, &11)));
+        assert_eq!(cursor.move_prev(), Some((&6, &6)));
+        assert_eq!(cursor.current(), Some((&6, &6)));
+
+        assert_eq!(cursor.seek(&7), Some((&11, &11)));
+        assert_eq!(cursor.seek(&124), None);
+        assert_eq!(cursor.move_prev(), Some((&123, &123)));
+    }
+
+    #[test]
+    fn ghost_position_wraps_around() {
+        let tree = demo_tree();
+        let mut cursor = tree.cursor();
+        assert_eq!(cursor.current(), None);
+
+        let keys: Vec<i32> = core::iter::from_fn(|| cursor.move_next())
+            .map(|(k, _)| *k)
+            .collect();
+        assert_eq!(keys, [1, 3, 5, 6, 11, 42, 65, 123]);
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.move_next(), Some((&1, &1)));
+        assert_eq!(cursor.move_prev(), None);
+        assert_eq!(cursor.move_prev(), Some((&123, &123)));
+
+        let empty: Tree<i32, i32> = Tree::new();
+        let mut cursor = empty.cursor();
+        assert_eq!(cursor.move_next(), None);
+        assert_eq!(cursor.move_prev(), None);
+    }
+}
@@ -0,0 +1,159 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Unbalanced tree with a limit on its depth, which rejects inserts
+//! instead of degenerating into a long chain on adversarial input.
+
+use core::cmp::Ordering;
+
+use crate::{InsertError, Tree};
+
+/**
+ * Tree, which keeps its height not greater than the configured limit.
+ * An insert, which would put a new node deeper, fails with
+ * `InsertError::DepthExceeded`, so the caller can rebalance the tree
+ * or reject the input.
+ */
+#[derive(Debug)]
+pub struct DepthLimitedTree<K, V> {
+    tree: Tree<K, V>,
+    max_depth: usize,
+}
+
+impl<K, V> DepthLimitedTree<K, V> {
+    /**
+     * Maximum number of nodes on a path from the root to a leaf.
+     */
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /**
+     * The underlying tree for read-only access.
+     */
+    pub fn tree(&self) -> &Tree<K, V> {
+        &self.tree
+    }
+
+    /**
+     * Number of key-value pairs in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /**
+     * Rebuild the tree into a balanced shape, which makes room for
+     * inserts, rejected because of the depth.
+     */
+    pub fn rebalance(&mut self) {
+        self.tree.rebalance();
+    }
+
+    /**
+     * Unwrap the tree and drop the limit.
+     */
+    pub fn into_inner(self) -> Tree<K, V> {
+        self.tree
+    }
+}
+
+impl<K, V> DepthLimitedTree<K, V>
+where
+    K: Ord,
+{
+    /**
+     * Insert key-value pair into tree. When the key is already present,
+     * its value is replaced and the previous value is returned. A new
+     * key, which would be placed deeper than the limit, is rejected
+     * and the tree stays unchanged.
+     */
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, InsertError> {
+        let mut depth = 1;
+        let mut subtree = self.tree.root.as_deref();
+        while let Some(node) = subtree {
+            subtree = match key.cmp(&node.key) {
+                Ordering::Equal => return Ok(self.tree.insert(key, value)),
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+            };
+            depth += 1;
+        }
+
+        if depth > self.max_depth {
+            return Err(InsertError::DepthExceeded);
+        }
+        Ok(self.tree.insert(key, value))
+    }
+
+    /**
+     * Remove the key from tree and return its value.
+     */
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.tree.remove(key)
+    }
+
+    /**
+     * Get reference to the value stored under the key.
+     */
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.tree.get(key)
+    }
+
+    /**
+     * Check is the key present in the tree.
+     */
+    pub fn contains(&self, key: &K) -> bool {
+        self.tree.contains(key)
+    }
+}
+
+impl<K, V> Tree<K, V>
+where
+    K: Ord,
+{
+    /**
+     * Create an empty tree, which rejects inserts deeper than
+     * `max_depth` levels.
+     */
+    pub fn with_max_depth(max_depth: usize) -> DepthLimitedTree<K, V> {
+        DepthLimitedTree {
+            tree: Tree::new(),
+            max_depth,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InsertError, Tree};
+
+    #[test]
+    fn ascending_run_stops_at_the_limit() {
+        let mut tree = Tree::with_max_depth(8);
+        for i in 1..=8 {
+            assert_eq!(tree.insert(i, i), Ok(None));
+        }
+
+        assert_eq!(tree.insert(9, 9), Err(InsertError::DepthExceeded));
+        assert_eq!(tree.len(), 8);
+        assert!(!tree.contains(&9));
+        assert_eq!(tree.tree().height(), 8);
+
+        // Existing keys are still replaced, they need no new level.
+        assert_eq!(tree.insert(8, 80), Ok(Some(8)));
+        assert_eq!(tree.get(&8), Some(&80));
+
+        tree.rebalance();
+        assert_eq!(tree.insert(9, 9), Ok(None));
+        assert_eq!(tree.tree().height(), 4);
+        assert_eq!(tree.remove(&1), Some(1));
+        assert!(tree.into_inner().keys().copied().eq(2..=9));
+    }
+}
@@ -0,0 +1,276 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Human readable rendering of the tree.
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+use crate::{Node, Tree};
+
+impl<K, V> fmt::Display for Tree<K, V>
+where
+    K: fmt::Display,
+    V: fmt::Display,
+{
+    /**
+     * Render the tree sideways: the root is on the left, right children
+     * are above their parent and marked by `/`, left children are below
+     * and marked by `\`. Each level of depth is indented by four spaces.
+     */
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Reverse in-order walk with an explicit stack of right spines.
+        let mut stack: Vec<(&Node<K, V>, usize, &str)> = Vec::new();
+        push_right_spine(&mut stack, self.root.as_deref(), 0, "");
+
+        while let Some((node, depth, marker)) = stack.pop() {
+            writeln!(
+                f,
+                "{:indent$}{}{}: {}",
+                "",
+                marker,
+                node.key,
+                node.value,
+                indent = depth * 4
+            )?;
+            push_right_spine(&mut stack, node.left.as_deref(), depth + 1, "\\");
+        }
+        Ok(())
+    }
+}
+
+fn push_right_spine<'a, K, V>(
+    stack: &mut Vec<(&'a Node<K, V>, usize, &'static str)>,
+    mut subtree: Option<&'a Node<K, V>>,
+    mut depth: usize,
+    mut marker: &'static str,
+) {
+    while let Some(node) = subtree {
+        stack.push((node, depth, marker));
+        subtree = node.right.as_deref();
+        depth += 1;
+        marker = "/";
+    }
+}
+
+/**
+ * Step of the pre-order walk for the one line structure.
+ */
+enum Step<'a, K, V> {
+    Node(&'a Node<K, V>, &'static str),
+    Empty,
+    Close,
+}
+
+impl<K, V> fmt::Debug for Tree<K, V>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    /**
+     * Entries in key order, like a map: `{1: 1, 3: 3}`. The alternate form
+     * `{:#?}` prints the structure instead: each node on its own line,
+     * indented by four spaces per level of depth and marked by `L` or `R`
+     * for left and right children.
+     */
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !f.alternate() {
+            return f.debug_map().entries(self.iter()).finish();
+        }
+
+        let mut stack: Vec<(&Node<K, V>, usize, &str)> = self
+            .root
+            .as_deref()
+            .map(|root| (root, 0, ""))
+            .into_iter()
+            .collect();
+        while let Some((node, depth, marker)) = stack.pop() {
+            writeln!(
+                f,
+                "{:indent$}{}{:?}: {:?}",
+                "",
+                marker,
+                node.key,
+                node.value,
+                indent = depth * 4
+            )?;
+            stack.extend(node.right.as_deref().map(|right| (right, depth + 1, "R ")));
+            stack.extend(node.left.as_deref().map(|left| (left, depth + 1, "L ")));
+        }
+        Ok(())
+    }
+}
+
+impl<K, V> Tree<K, V>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    /**
+     * Structure of the tree on one line: nodes in pre-order as
+     * `(key: value left right)`, with `_` for a missing child.
+     */
+    pub fn to_structure_string(&self) -> String {
+        let mut stack: Vec<Step<'_, K, V>> = match self.root.as_deref() {
+            Some(root) => vec![Step::Node(root, "")],
+            None => return String::from("()"),
+        };
+
+        // Writing into a string cannot fail.
+        let mut structure = String::new();
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Node(node, separator) => {
+                    let _ = write!(structure, "{}({:?}: {:?}", separator, node.key, node.value);
+                    if node.left.is_none() && node.right.is_none() {
+                        structure.push(')');
+                        continue;
+                    }
+                    stack.push(Step::Close);
+                    for child in [node.right.as_deref(), node.left.as_deref()] {
+                        stack.push(child.map_or(Step::Empty, |child| Step::Node(child, " ")));
+                    }
+                }
+                Step::Empty => structure.push_str(" _"),
+                Step::Close => structure.push(')'),
+            }
+        }
+        structure
+    }
+}
+
+impl<K, V> Tree<K, V>
+where
+    K: fmt::Display,
+{
+    /**
+     * Render the tree as a Graphviz digraph, with edges to children
+     * labeled by `L` and `R`. When a node has only one child, an invisible
+     * node takes the place of the missing one, so `dot` keeps left and right
+     * children on their sides.
+     */
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph tree {\n");
+        let mut stack: Vec<(&Node<K, V>, usize)> = Vec::new();
+        if let Some(root) = self.root.as_deref() {
+            stack.push((root, 0));
+        }
+        let mut next_id = 1;
+
+        // Writing into a string cannot fail.
+        while let Some((node, id)) = stack.pop() {
+            let label = node
+                .key
+                .to_string()
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"");
+            let _ = writeln!(dot, "    n{} [label=\"{}\"];", id, label);
+
+            let has_child = node.left.is_some() || node.right.is_some();
+            for (child, side) in [(node.left.as_deref(), "L"), (node.right.as_deref(), "R")] {
+                match child {
+                    Some(child) => {
+                        let _ = writeln!(dot, "    n{} -> n{} [label=\"{}\"];", id, next_id, side);
+                        stack.push((child, next_id));
+                        next_id += 1;
+                    }
+                    None if has_child => {
+                        let _ = writeln!(dot, "    n{}{} [style=invis];", id, side);
+                        let _ = writeln!(dot, "    n{} -> n{}{} [style=invis];", id, id, side);
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Tree;
+
+    #[test]
+    fn display_demo_tree() {
+        let mut tree = Tree::new();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i);
+        }
+
+        let expected = concat!(
+            "        /123: 123\n",
+            "    /65: 65\n",
+            "                /42: 42\n",
+            "            /11: 11\n",
+            "        \\6: 6\n",
+            "5: 5\n",
+            "    \\3: 3\n",
+            "        \\1: 1\n",
+        );
+        assert_eq!(tree.to_string(), expected);
+    }
+
+    #[test]
+    fn dot_has_edge_per_child() {
+        let mut tree = Tree::new();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i);
+        }
+
+        let dot = tree.to_dot();
+        assert!(dot.starts_with("digraph tree {\n"));
+        assert!(dot.ends_with("}\n"));
+        let edges = dot
+            .lines()
+            .filter(|line| line.contains("[label=\"L\"]") || line.contains("[label=\"R\"]"));
+        assert_eq!(edges.count(), tree.len() - 1);
+        assert!(dot.contains("    n0 [label=\"5\"];\n"));
+        assert!(dot.contains("    n0 -> n1 [label=\"L\"];\n"));
+
+        let empty: Tree<i32, i32> = Tree::new();
+        assert_eq!(empty.to_dot(), "digraph tree {\n}\n");
+    }
+
+    #[test]
+    fn display_empty_tree() {
+        let tree: Tree<i32, i32> = Tree::new();
+        assert_eq!(tree.to_string(), "");
+    }
+
+    #[test]
+    fn debug_demo_tree() {
+        let mut tree = Tree::new();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i);
+        }
+
+        assert_eq!(
+            format!("{:?}", tree),
+            "{1: 1, 3: 3, 5: 5, 6: 6, 11: 11, 42: 42, 65: 65, 123: 123}"
+        );
+        assert_eq!(
+            tree.to_structure_string(),
+            "(5: 5 (3: 3 (1: 1) _) (65: 65 (6: 6 _ (11: 11 _ (42: 42))) (123: 123)))"
+        );
+
+        let expected = concat!(
+            "5: 5\n",
+            "    L 3: 3\n",
+            "        L 1: 1\n",
+            "    R 65: 65\n",
+            "        L 6: 6\n",
+            "            R 11: 11\n",
+            "                R 42: 42\n",
+            "        R 123: 123\n",
+        );
+        assert_eq!(format!("{:#?}", tree), expected);
+
+        let empty: Tree<i32, i32> = Tree::new();
+        assert_eq!(format!("{:?}", empty), "{}");
+        assert_eq!(empty.to_structure_string(), "()");
+        assert_eq!(format!("{:#?}", empty), "");
+    }
+}
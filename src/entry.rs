@@ -0,0 +1,300 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Map-style entry API, which locates the place of a key only once.
+
+use alloc::boxed::Box;
+use core::cmp::Ordering;
+use core::ptr;
+
+use crate::{Node, Tree};
+
+/**
+ * View into a single place of the tree, which is either occupied
+ * by an existing entry or vacant.
+ */
+#[derive(Debug)]
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+/**
+ * Place of an existing entry.
+ */
+#[derive(Debug)]
+pub struct OccupiedEntry<'a, K, V> {
+    tree: &'a mut Tree<K, V>,
+    node: *mut Node<K, V>,
+}
+
+/**
+ * Place, where a new entry will be attached: under the parent node
+ * on the given side, or as the root, when there is no parent.
+ */
+#[derive(Debug)]
+pub struct VacantEntry<'a, K, V> {
+    tree: &'a mut Tree<K, V>,
+    key: K,
+    parent: Option<*mut Node<K, V>>,
+    side: Ordering,
+}
+
+impl<K, V> Tree<K, V>
+where
+    K: Ord,
+{
+    /**
+     * Get the entry for the key for in-place manipulation.
+     */
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        let mut parent = None;
+        let mut side = Ordering::Equal;
+        let mut subtree = self.root.as_deref_mut().map(|node| node as *mut Node<K, V>);
+
+        while let Some(node_ptr) = subtree {
+            // The tree is borrowed exclusively, so the node is alive and
+            // no other reference to it exists.
+            let node = unsafe { &mut *node_ptr };
+            side = key.cmp(&node.key);
+            subtree = match side {
+                Ordering::Equal => {
+                    return Entry::Occupied(OccupiedEntry {
+                        tree: self,
+                        node: node_ptr,
+                    })
+                }
+                Ordering::Less => node.left.as_deref_mut().map(|node| node as *mut _),
+                Ordering::Greater => node.right.as_deref_mut().map(|node| node as *mut _),
+            };
+            parent = Some(node_ptr);
+        }
+
+        Entry::Vacant(VacantEntry {
+            tree: self,
+            key,
+            parent,
+            side,
+        })
+    }
+}
+
+impl<'a, K, V> Entry<'a, K, V> {
+    /**
+     * Key of the entry.
+     */
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /**
+     * Insert the default value, when the entry is vacant, and return
+     * mutable reference to the value.
+     */
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    /**
+     * Insert the value returned by the function, when the entry is vacant,
+     * and return mutable reference to the value.
+     */
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /**
+     * Modify the value of an occupied entry, a vacant entry is left as is.
+     */
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(entry) => {
+                // The node is owned by the exclusively borrowed tree.
+                f(unsafe { &mut (*entry.node).value });
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    /**
+     * Key of the entry.
+     */
+    pub fn key(&self) -> &K {
+        unsafe { &(*self.node).key }
+    }
+
+    /**
+     * Reference to the value of the entry.
+     */
+    pub fn get(&self) -> &V {
+        unsafe { &(*self.node).value }
+    }
+
+    /**
+     * Mutable reference to the value of the entry.
+     */
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { &mut (*self.node).value }
+    }
+
+    /**
+     * Convert the entry into mutable reference to its value.
+     */
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe { &mut (*self.node).value }
+    }
+
+    /**
+     * Replace the value of the entry and return the previous one.
+     */
+    pub fn insert(&mut self, value: V) -> V {
+        core::mem::replace(self.get_mut(), value)
+    }
+
+    /**
+     * Unlink the entry from the tree, like `Tree::remove`, and return
+     * its value.
+     */
+    pub fn remove(self) -> V
+    where
+        K: Ord,
+    {
+        // The node and its parent are owned by the exclusively borrowed
+        // tree, and the slot is the only link to the node.
+        let parent = unsafe { (*self.node).parent };
+        let slot = match parent {
+            None => &mut self.tree.root,
+            Some(parent_ptr) => {
+                let parent = unsafe { &mut *parent_ptr };
+                let is_left = parent
+                    .left
+                    .as_deref()
+                    .is_some_and(|left| ptr::eq(left, self.node));
+                if is_left {
+                    &mut parent.left
+                } else {
+                    &mut parent.right
+                }
+            }
+        };
+
+        let (_, value) = Tree::unlink(slot).expect("occupied entry has a node");
+        self.tree.len -= 1;
+        value
+    }
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V> {
+    /**
+     * Key, which will be inserted.
+     */
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /**
+     * Attach new node with the key and the value at the place of the entry
+     * and return mutable reference to the value.
+     */
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.tree.len += 1;
+
+        let node: *mut Node<K, V> = match self.parent {
+            None => &mut **self
+                .tree
+                .root
+                .insert(Box::new(Node::new_root(self.key, value))),
+
+            Some(parent_ptr) => {
+                // The parent is owned by the exclusively borrowed tree.
+                let parent = unsafe { &mut *parent_ptr };
+                let new_node = Box::new(Node::new_leaf(self.key, value, parent_ptr));
+                let node: *mut Node<K, V> = match self.side {
+                    Ordering::Less => &mut **parent.left.insert(new_node),
+                    _ => &mut **parent.right.insert(new_node),
+                };
+                Node::fix_sizes_upwards(Some(parent_ptr));
+                node
+            }
+        };
+        // The new node is owned by the tree, which stays borrowed for 'a.
+        unsafe { &mut (*node).value }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Entry, Tree};
+
+    #[test]
+    fn entry_counts_occurrences() {
+        let mut tree = Tree::new();
+        for word in "b a c a b a".split(' ') {
+            *tree.entry(word).or_insert(0) += 1;
+        }
+
+        let counts: Vec<(&str, i32)> = tree.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(counts, vec![("a", 3), ("b", 2), ("c", 1)]);
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.successor(&"a"), Some(&"b"));
+    }
+
+    #[test]
+    fn entry_and_modify_or_insert_with() {
+        let mut tree = Tree::new();
+        tree.insert(6, 6);
+
+        tree.entry(6).and_modify(|v| *v *= 10).or_insert(0);
+        tree.entry(7).and_modify(|v| *v *= 10).or_insert_with(|| 7);
+        assert_eq!(tree.get(&6), Some(&60));
+        assert_eq!(tree.get(&7), Some(&7));
+        assert_eq!(tree.entry(7).key(), &7);
+    }
+
+    #[test]
+    fn remove_occupied_entry_after_inspection() {
+        let mut tree: Tree<i32, i32> = [5, 3, 65, 123, 6, 11, 1, 42]
+            .into_iter()
+            .map(|i| (i, i))
+            .collect();
+        for key in [6, 3, 5] {
+            if let Entry::Occupied(entry) = tree.entry(key) {
+                if *entry.get() > 4 {
+                    entry.remove();
+                }
+            }
+        }
+
+        assert!(tree.keys().copied().eq([1, 3, 11, 42, 65, 123]));
+        assert_eq!(tree.len(), 6);
+        assert!(tree.is_valid_bst());
+        assert_eq!(tree.verify_and_repair(), 0);
+        assert_eq!(tree.rank(&42), 3);
+        assert_eq!(tree.nth(4), Some((&65, &65)));
+    }
+
+    #[test]
+    fn occupied_entry_get_and_insert() {
+        let mut tree = Tree::new();
+        tree.insert(11, 11);
+        let Entry::Occupied(mut entry) = tree.entry(11) else {
+            panic!("key 11 is present");
+        };
+        *entry.get_mut() += 1;
+        assert_eq!(entry.insert(110), 12);
+        assert_eq!(entry.get(), &110);
+        assert_eq!(tree.get(&11), Some(&110));
+        assert!(matches!(tree.entry(12), Entry::Vacant(_)));
+    }
+}
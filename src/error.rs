@@ -0,0 +1,138 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Errors reported by the tree.
+
+use alloc::string::String;
+use core::fmt;
+
+/**
+ * Error of parsing a tree from its pre-order encoding.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The token is neither an entry `key:value` nor the null marker `#`.
+    InvalidToken(String),
+    /// The key of the entry cannot be parsed.
+    InvalidKey(String),
+    /// The value of the entry cannot be parsed.
+    InvalidValue(String),
+    /// The input ends before all subtrees are complete.
+    UnexpectedEnd,
+    /// The input contains tokens after the complete tree.
+    TrailingInput(String),
+    /// Keys are not in search order, or a key is duplicated.
+    NotOrdered,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidToken(token) => write!(f, "invalid token: {:?}", token),
+            ParseError::InvalidKey(key) => write!(f, "invalid key: {:?}", key),
+            ParseError::InvalidValue(value) => write!(f, "invalid value: {:?}", value),
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            ParseError::TrailingInput(token) => write!(f, "trailing input at {:?}", token),
+            ParseError::NotOrdered => write!(f, "keys are not in search order"),
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+/**
+ * Error of inserting an entry into a tree.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertError {
+    /// The key is not comparable with other keys, like a `NaN` float.
+    Incomparable,
+    /// The new node would be deeper than the limit of the tree.
+    DepthExceeded,
+}
+
+impl fmt::Display for InsertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InsertError::Incomparable => write!(f, "key is not comparable"),
+            InsertError::DepthExceeded => write!(f, "maximum depth of the tree is exceeded"),
+        }
+    }
+}
+
+impl core::error::Error for InsertError {}
+
+/**
+ * Error of building a tree from pairs, which must be sorted by key.
+ * Holds the position and the key of the first pair, which does not
+ * follow the previous one.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortedError<K> {
+    /// The key is less than the key of the previous pair.
+    OutOfOrder { index: usize, key: K },
+    /// The key is equal to the key of the previous pair.
+    Duplicate { index: usize, key: K },
+}
+
+impl<K: fmt::Debug> fmt::Display for SortedError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortedError::OutOfOrder { index, key } => {
+                write!(f, "key {:?} at index {} is out of order", key, index)
+            }
+            SortedError::Duplicate { index, key } => {
+                write!(f, "key {:?} at index {} is duplicated", key, index)
+            }
+        }
+    }
+}
+
+impl<K: fmt::Debug> core::error::Error for SortedError<K> {}
+
+/**
+ * Error of rebuilding a tree from its level-order array.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelArrayError {
+    /// The entry at the index has no parent entry.
+    Orphan { index: usize },
+    /// Keys are not in search order, or a key is duplicated.
+    NotOrdered,
+    /// The tree is too high for the array, which needs `2^height - 1` slots.
+    TooHigh { height: usize },
+}
+
+impl fmt::Display for LevelArrayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelArrayError::Orphan { index } => {
+                write!(f, "entry at index {} has no parent", index)
+            }
+            LevelArrayError::NotOrdered => write!(f, "keys are not in search order"),
+            LevelArrayError::TooHigh { height } => {
+                write!(
+                    f,
+                    "tree of height {} is too high for the level array",
+                    height
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for LevelArrayError {}
+
+/**
+ * Error of building a tree from pairs, which must have unique keys.
+ * Holds the key, which occurs more than once.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKey<K>(pub K);
+
+impl<K: fmt::Debug> fmt::Display for DuplicateKey<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key {:?} is duplicated", self.0)
+    }
+}
+
+impl<K: fmt::Debug> core::error::Error for DuplicateKey<K> {}
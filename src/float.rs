@@ -0,0 +1,112 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Float keys. Keys of the tree must be totally ordered, but `NaN` is not
+//! comparable with any float, so floats are wrapped into a key type,
+//! which cannot hold `NaN`.
+
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::{InsertError, Tree};
+
+/**
+ * Float, which is never `NaN`, so it is totally ordered. Zeroes
+ * of both signs are the same key, like they are equal floats.
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatKey(f64);
+
+impl FloatKey {
+    /**
+     * Wrap the float, or return an error for `NaN`.
+     */
+    pub fn new(value: f64) -> Result<Self, InsertError> {
+        match value.is_nan() {
+            true => Err(InsertError::Incomparable),
+            false => Ok(Self(value)),
+        }
+    }
+
+    /**
+     * The wrapped float.
+     */
+    pub fn get(self) -> f64 {
+        self.0
+    }
+}
+
+impl TryFrom<f64> for FloatKey {
+    type Error = InsertError;
+
+    fn try_from(value: f64) -> Result<Self, InsertError> {
+        Self::new(value)
+    }
+}
+
+impl Eq for FloatKey {}
+
+impl PartialOrd for FloatKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FloatKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Both floats are not NaN, so they are always comparable.
+        self.0.partial_cmp(&other.0).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl fmt::Display for FloatKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<V> Tree<FloatKey, V> {
+    /**
+     * Insert the float key with the value like `insert`, or return
+     * an error and keep the tree unchanged, when the key is `NaN`.
+     */
+    pub fn checked_insert(&mut self, key: f64, value: V) -> Result<Option<V>, InsertError> {
+        Ok(self.insert(FloatKey::new(key)?, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FloatKey, InsertError, Tree};
+
+    #[test]
+    fn checked_insert_rejects_nan() {
+        let mut tree = Tree::new();
+        for key in [2.5, -1.0, 0.0, f64::INFINITY] {
+            assert_eq!(tree.checked_insert(key, key), Ok(None));
+        }
+        assert_eq!(tree.checked_insert(-0.0, -0.0), Ok(Some(0.0)));
+
+        let before = tree.clone();
+        assert_eq!(
+            tree.checked_insert(f64::NAN, 1.0),
+            Err(InsertError::Incomparable)
+        );
+        assert_eq!(tree, before);
+        assert!(tree.is_valid_bst());
+
+        let keys: Vec<f64> = tree.keys().map(|key| key.get()).collect();
+        assert_eq!(keys, [-1.0, 0.0, 2.5, f64::INFINITY]);
+        assert!(tree.contains(&FloatKey::new(2.5).unwrap()));
+    }
+
+    #[test]
+    fn float_key_from_float() {
+        assert!(FloatKey::try_from(f64::NAN).is_err());
+        assert_eq!(FloatKey::try_from(1.5).map(FloatKey::get), Ok(1.5));
+        assert!(FloatKey::new(-0.0) == FloatKey::new(0.0));
+        assert_eq!(
+            FloatKey::new(f64::NEG_INFINITY).unwrap().to_string(),
+            "-inf"
+        );
+    }
+}
@@ -0,0 +1,763 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Iterators over the tree.
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::{Node, Tree};
+
+/**
+ * Borrowing iterator, which yields references to keys and values
+ * in ascending key order, or in descending order when reversed.
+ *
+ * The front stack holds the left spine and the back stack holds the
+ * right spine of not yet visited subtrees. The iterator is exhausted,
+ * when the next node on one end was already yielded from other end.
+ */
+#[derive(Debug, Clone)]
+pub struct Iter<'a, K, V> {
+    front: Vec<&'a Node<K, V>>,
+    back: Vec<&'a Node<K, V>>,
+    last_front: Option<&'a Node<K, V>>,
+    last_back: Option<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    pub(crate) fn new(root: Option<&'a Node<K, V>>) -> Self {
+        let mut iter = Self {
+            front: Vec::new(),
+            back: Vec::new(),
+            last_front: None,
+            last_back: None,
+        };
+        iter.push_left_spine(root);
+        iter.push_right_spine(root);
+        iter
+    }
+
+    fn push_left_spine(&mut self, mut subtree: Option<&'a Node<K, V>>) {
+        while let Some(node) = subtree {
+            self.front.push(node);
+            subtree = node.left.as_deref();
+        }
+    }
+
+    fn push_right_spine(&mut self, mut subtree: Option<&'a Node<K, V>>) {
+        while let Some(node) = subtree {
+            self.back.push(node);
+            subtree = node.right.as_deref();
+        }
+    }
+
+    fn is_yielded(node: &Node<K, V>, last: Option<&Node<K, V>>) -> bool {
+        last.is_some_and(|last| core::ptr::eq(last, node))
+    }
+
+    fn finish(&mut self) {
+        self.front.clear();
+        self.back.clear();
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.front.pop()?;
+        if Self::is_yielded(node, self.last_back) {
+            self.finish();
+            return None;
+        }
+
+        self.push_left_spine(node.right.as_deref());
+        self.last_front = Some(node);
+        Some((&node.key, &node.value))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.back.pop()?;
+        if Self::is_yielded(node, self.last_front) {
+            self.finish();
+            return None;
+        }
+
+        self.push_right_spine(node.left.as_deref());
+        self.last_back = Some(node);
+        Some((&node.key, &node.value))
+    }
+}
+
+/**
+ * Iterator over entries with keys in closed interval, in ascending key
+ * order, or in descending order when reversed.
+ *
+ * Stacks of the inner iterator are positioned at the first and the last
+ * entry of the interval, and the entries just outside of the interval
+ * are marked as already yielded, so each end stops at the boundary.
+ */
+#[derive(Debug, Clone)]
+pub struct Range<'a, K, V> {
+    iter: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Range<'a, K, V>
+where
+    K: Ord,
+{
+    pub(crate) fn new(root: Option<&'a Node<K, V>>, low: &K, high: &K) -> Self {
+        let mut iter = Iter::new(None);
+        if low > high {
+            return Self { iter };
+        }
+
+        let mut subtree = root;
+        while let Some(node) = subtree {
+            if node.key < *low {
+                iter.last_front = Some(node);
+                subtree = node.right.as_deref();
+            } else {
+                iter.front.push(node);
+                subtree = node.left.as_deref();
+            }
+        }
+
+        let mut subtree = root;
+        while let Some(node) = subtree {
+            if node.key > *high {
+                iter.last_back = Some(node);
+                subtree = node.left.as_deref();
+            } else {
+                iter.back.push(node);
+                subtree = node.right.as_deref();
+            }
+        }
+
+        Self { iter }
+    }
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Range<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+/**
+ * In-order iterator, which yields the depth of each node together with
+ * its entry. The stack holds the left spine of not yet visited subtrees
+ * with depths of the nodes.
+ */
+#[derive(Debug, Clone)]
+pub struct IterWithDepth<'a, K, V> {
+    stack: Vec<(&'a Node<K, V>, usize)>,
+}
+
+impl<'a, K, V> IterWithDepth<'a, K, V> {
+    pub(crate) fn new(root: Option<&'a Node<K, V>>) -> Self {
+        let mut iter = Self { stack: Vec::new() };
+        iter.push_left_spine(root, 0);
+        iter
+    }
+
+    fn push_left_spine(&mut self, mut subtree: Option<&'a Node<K, V>>, mut depth: usize) {
+        while let Some(node) = subtree {
+            self.stack.push((node, depth));
+            subtree = node.left.as_deref();
+            depth += 1;
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for IterWithDepth<'a, K, V> {
+    type Item = (usize, &'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, depth) = self.stack.pop()?;
+        self.push_left_spine(node.right.as_deref(), depth + 1);
+        Some((depth, &node.key, &node.value))
+    }
+}
+
+/**
+ * Iterator in pre-order: a node is yielded before its left subtree,
+ * and the left subtree before the right one.
+ *
+ * The stack holds roots of not yet visited subtrees, the right child
+ * is pushed first, so the left one is popped first.
+ */
+#[derive(Debug, Clone)]
+pub struct Preorder<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Preorder<'a, K, V> {
+    pub(crate) fn new(root: Option<&'a Node<K, V>>) -> Self {
+        Self {
+            stack: root.into_iter().collect(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Preorder<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.right.as_deref());
+        self.stack.extend(node.left.as_deref());
+        Some((&node.key, &node.value))
+    }
+}
+
+/**
+ * Iterator in post-order: left subtree, then right subtree, then the node.
+ *
+ * The stack holds the path from the root to the next node, which is
+ * the first leaf found by descending to the left child, or to the right
+ * child, when there is no left one. When a left child is yielded, the
+ * same descent is repeated from the right sibling, if any.
+ */
+#[derive(Debug, Clone)]
+pub struct Postorder<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Postorder<'a, K, V> {
+    pub(crate) fn new(root: Option<&'a Node<K, V>>) -> Self {
+        let mut iter = Self { stack: Vec::new() };
+        iter.push_leaf_path(root);
+        iter
+    }
+
+    fn push_leaf_path(&mut self, mut subtree: Option<&'a Node<K, V>>) {
+        while let Some(node) = subtree {
+            self.stack.push(node);
+            subtree = node.left.as_deref().or(node.right.as_deref());
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Postorder<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        if let Some(parent) = self.stack.last() {
+            let is_left_child = parent
+                .left
+                .as_deref()
+                .is_some_and(|left| core::ptr::eq(left, node));
+            if is_left_child {
+                self.push_leaf_path(parent.right.as_deref());
+            }
+        }
+        Some((&node.key, &node.value))
+    }
+}
+
+/**
+ * Iterator in level order: the root first, then nodes of each next
+ * depth from left to right. The queue holds nodes of the current level,
+ * followed by already found nodes of the next level.
+ */
+#[derive(Debug, Clone)]
+pub struct LevelOrder<'a, K, V> {
+    queue: VecDeque<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> LevelOrder<'a, K, V> {
+    pub(crate) fn new(root: Option<&'a Node<K, V>>) -> Self {
+        Self {
+            queue: root.into_iter().collect(),
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for LevelOrder<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.queue.pop_front()?;
+        self.queue.extend(node.left.as_deref());
+        self.queue.extend(node.right.as_deref());
+        Some((&node.key, &node.value))
+    }
+}
+
+/**
+ * Mutable iterator, which yields references to keys and mutable references
+ * to values in ascending key order. Keys stay immutable, so the order
+ * of the tree cannot be broken.
+ *
+ * Each stack entry is a node split into its key, its value, and its
+ * right subtree, which is not visited yet.
+ */
+#[derive(Debug)]
+pub struct IterMut<'a, K, V> {
+    stack: Vec<SplitNode<'a, K, V>>,
+}
+
+type SplitNode<'a, K, V> = (&'a K, &'a mut V, Option<&'a mut Node<K, V>>);
+
+impl<'a, K, V> IterMut<'a, K, V> {
+    pub(crate) fn new(root: Option<&'a mut Node<K, V>>) -> Self {
+        let mut iter = Self { stack: Vec::new() };
+        iter.push_left_spine(root);
+        iter
+    }
+
+    fn push_left_spine(&mut self, mut subtree: Option<&'a mut Node<K, V>>) {
+        while let Some(node) = subtree {
+            let Node {
+                key,
+                value,
+                left,
+                right,
+                ..
+            } = node;
+            self.stack.push((key, value, right.as_deref_mut()));
+            subtree = left.as_deref_mut();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value, right) = self.stack.pop()?;
+        self.push_left_spine(right);
+        Some((key, value))
+    }
+}
+
+/**
+ * Mutable iterator over entries with keys in closed interval, in ascending
+ * key order. Subtrees left of the interval are skipped, when the start is
+ * searched, and the iterator stops after the counted number of entries,
+ * so subtrees right of the interval are not visited.
+ */
+#[derive(Debug)]
+pub struct RangeMut<'a, K, V> {
+    iter: IterMut<'a, K, V>,
+    remaining: usize,
+}
+
+impl<'a, K, V> RangeMut<'a, K, V>
+where
+    K: Ord,
+{
+    pub(crate) fn new(root: Option<&'a mut Node<K, V>>, low: &K, len: usize) -> Self {
+        let mut iter = IterMut { stack: Vec::new() };
+
+        let mut subtree = root;
+        while let Some(node) = subtree {
+            let Node {
+                key,
+                value,
+                left,
+                right,
+                ..
+            } = node;
+            if *key < *low {
+                subtree = right.as_deref_mut();
+            } else {
+                iter.stack.push((key, value, right.as_deref_mut()));
+                subtree = left.as_deref_mut();
+            }
+        }
+
+        Self {
+            iter,
+            remaining: len,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for RangeMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for RangeMut<'_, K, V> {}
+
+/**
+ * Consuming iterator, which yields key-value pairs in ascending key order.
+ *
+ * The stack holds the left spine of not yet visited subtrees, so memory use
+ * is proportional to the height of the tree and no recursion is needed.
+ * Each node is freed, when its entry is yielded, and nodes, which are not
+ * yielded, are freed without recursion, when the iterator is dropped.
+ */
+#[derive(Debug)]
+pub struct IntoIter<K, V> {
+    stack: Vec<Box<Node<K, V>>>,
+}
+
+impl<K, V> IntoIter<K, V> {
+    pub(crate) fn new(root: Option<Box<Node<K, V>>>) -> Self {
+        let mut iter = Self { stack: Vec::new() };
+        iter.push_left_spine(root);
+        iter
+    }
+
+    fn push_left_spine(&mut self, mut subtree: Option<Box<Node<K, V>>>) {
+        while let Some(mut node) = subtree {
+            subtree = node.left.take();
+            self.stack.push(node);
+        }
+    }
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut node = self.stack.pop()?;
+        self.push_left_spine(node.right.take());
+        Some((node.key, node.value))
+    }
+}
+
+impl<K, V> Drop for IntoIter<K, V> {
+    fn drop(&mut self) {
+        while let Some(mut node) = self.stack.pop() {
+            self.stack.extend(node.left.take());
+            self.stack.extend(node.right.take());
+        }
+    }
+}
+
+impl<K, V> IntoIterator for Tree<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        IntoIter::new(self.root.take())
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a Tree<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    /**
+     * Iterate over the borrowed tree like `iter`, so the tree can be used
+     * in a `for` loop by reference, by mutable reference and by value.
+     *
+     * ```
+     * use basic_binary_tree::Tree;
+     *
+     * let mut tree: Tree<i32, i32> = [5, 3, 65, 123, 6, 11, 3, 1, 5, 42]
+     *     .into_iter()
+     *     .map(|i| (i, i))
+     *     .collect();
+     *
+     * let mut sum = 0;
+     * for (_, value) in &tree {
+     *     sum += value;
+     * }
+     * assert_eq!(sum, 256);
+     *
+     * for (key, value) in &mut tree {
+     *     *value = key * 10;
+     * }
+     *
+     * let mut values = Vec::new();
+     * for (_, value) in tree {
+     *     values.push(value);
+     * }
+     * assert_eq!(values, [10, 30, 50, 60, 110, 420, 650, 1230]);
+     * ```
+     */
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut Tree<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Tree;
+
+    fn demo_tree() -> Tree<i32, i32> {
+        let mut tree = Tree::new();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i);
+        }
+        tree
+    }
+
+    #[test]
+    fn iter_yields_references_in_key_order() {
+        let tree = demo_tree();
+
+        let keys: Vec<i32> = tree.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![1, 3, 5, 6, 11, 42, 65, 123]);
+        assert!(tree.iter().all(|(k, v)| k == v));
+    }
+
+    #[test]
+    fn iter_rev_yields_descending_order() {
+        let tree = demo_tree();
+
+        let keys: Vec<i32> = tree.iter().rev().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![123, 65, 42, 11, 6, 5, 3, 1]);
+    }
+
+    #[test]
+    fn iter_from_both_ends_meets_in_the_middle() {
+        let tree = demo_tree();
+        let mut iter = tree.iter();
+
+        assert_eq!(iter.next(), Some((&1, &1)));
+        assert_eq!(iter.next_back(), Some((&123, &123)));
+        assert_eq!(iter.next(), Some((&3, &3)));
+        assert_eq!(iter.next_back(), Some((&65, &65)));
+        assert_eq!(iter.next_back(), Some((&42, &42)));
+        assert_eq!(iter.next(), Some((&5, &5)));
+        assert_eq!(iter.next(), Some((&6, &6)));
+        assert_eq!(iter.next_back(), Some((&11, &11)));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn range_yields_keys_within_closed_interval() {
+        let tree = demo_tree();
+
+        let keys: Vec<i32> = tree.range(&5, &42).map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![5, 6, 11, 42]);
+
+        let keys: Vec<i32> = tree.range(&4, &41).map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![5, 6, 11]);
+
+        let keys: Vec<i32> = tree.range(&0, &1000).map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![1, 3, 5, 6, 11, 42, 65, 123]);
+    }
+
+    #[test]
+    fn range_reversed_and_empty() {
+        let tree = demo_tree();
+
+        let keys: Vec<i32> = tree.range(&5, &42).rev().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![42, 11, 6, 5]);
+
+        assert_eq!(tree.range(&42, &5).next(), None);
+        assert_eq!(tree.range(&7, &10).next(), None);
+        assert_eq!(tree.range(&7, &10).next_back(), None);
+        assert_eq!(tree.range(&200, &300).next(), None);
+    }
+
+    #[test]
+    fn range_rev_yields_keys_in_descending_order() {
+        let tree = demo_tree();
+
+        let keys: Vec<i32> = tree.range_rev(&5, &42).map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![42, 11, 6, 5]);
+
+        let keys: Vec<i32> = tree.range_rev(&4, &64).take(2).map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![42, 11]);
+        assert_eq!(tree.range_rev(&42, &5).next(), None);
+    }
+
+    #[test]
+    fn iter_with_depth_reports_depth_of_nodes() {
+        let tree = demo_tree();
+
+        let depths: Vec<(usize, i32)> = tree.iter_with_depth().map(|(d, k, _)| (d, *k)).collect();
+        assert_eq!(
+            depths,
+            vec![
+                (2, 1),
+                (1, 3),
+                (0, 5),
+                (2, 6),
+                (3, 11),
+                (4, 42),
+                (1, 65),
+                (2, 123)
+            ]
+        );
+        assert_eq!(depths.iter().map(|(d, _)| d + 1).max(), Some(tree.height()));
+        for (depth, key, _) in tree.iter_with_depth() {
+            assert_eq!(tree.depth_of(key), Some(depth));
+        }
+    }
+
+    #[test]
+    fn preorder_yields_root_first() {
+        let tree = demo_tree();
+
+        let keys: Vec<i32> = tree.preorder().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![5, 3, 1, 65, 6, 11, 42, 123]);
+        assert_eq!(Tree::<i32, i32>::new().preorder().next(), None);
+    }
+
+    #[test]
+    fn postorder_yields_children_before_parents() {
+        let tree = demo_tree();
+
+        let keys: Vec<i32> = tree.postorder().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![1, 3, 42, 11, 6, 123, 65, 5]);
+        assert_eq!(Tree::<i32, i32>::new().postorder().next(), None);
+
+        let tree: Tree<i32, i32> = [2, 1, 3].into_iter().map(|i| (i, i)).collect();
+        let keys: Vec<i32> = tree.postorder().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![1, 3, 2]);
+
+        let tree: Tree<i32, i32> = [3, 1, 2, 5, 4].into_iter().map(|i| (i, i)).collect();
+        let keys: Vec<i32> = tree.postorder().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![2, 1, 4, 5, 3]);
+    }
+
+    #[test]
+    fn level_order_groups_nodes_by_depth() {
+        let tree = demo_tree();
+
+        let keys: Vec<i32> = tree.level_order().map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![5, 3, 65, 1, 6, 123, 11, 42]);
+
+        let depths: Vec<usize> = keys.iter().map(|k| tree.depth_of(k).unwrap()).collect();
+        assert!(depths.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(Tree::<i32, i32>::new().level_order().next(), None);
+    }
+
+    #[test]
+    fn iter_mut_updates_values_in_place() {
+        let mut tree = demo_tree();
+
+        for (k, v) in tree.iter_mut() {
+            *v += k * 100;
+        }
+
+        let pairs: Vec<(i32, i32)> = tree.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (1, 101),
+                (3, 303),
+                (5, 505),
+                (6, 606),
+                (11, 1111),
+                (42, 4242),
+                (65, 6565),
+                (123, 12423)
+            ]
+        );
+    }
+
+    #[test]
+    fn range_mut_changes_only_values_in_range() {
+        let mut tree = demo_tree();
+
+        let keys: Vec<i32> = tree
+            .range_mut(&5, &11)
+            .map(|(k, v)| {
+                *v = 0;
+                *k
+            })
+            .collect();
+        assert_eq!(keys, [5, 6, 11]);
+        assert!(tree.iter().map(|(k, v)| (*k, *v)).eq([
+            (1, 1),
+            (3, 3),
+            (5, 0),
+            (6, 0),
+            (11, 0),
+            (42, 42),
+            (65, 65),
+            (123, 123)
+        ]));
+
+        assert_eq!(tree.range_mut(&7, &10).count(), 0);
+        assert_eq!(tree.range_mut(&11, &5).count(), 0);
+        assert_eq!(tree.range_mut(&0, &1_000).len(), 8);
+    }
+
+    #[test]
+    fn into_iter_yields_pairs_in_key_order() {
+        let mut tree = Tree::new();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i * 10);
+        }
+
+        let pairs: Vec<(i32, i32)> = tree.into_iter().collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (1, 10),
+                (3, 30),
+                (5, 50),
+                (6, 60),
+                (11, 110),
+                (42, 420),
+                (65, 650),
+                (123, 1230)
+            ]
+        );
+    }
+
+    #[test]
+    fn into_iter_of_empty_tree() {
+        let tree: Tree<i32, i32> = Tree::new();
+        assert_eq!(tree.into_iter().next(), None);
+    }
+
+    #[test]
+    fn into_iter_frees_yielded_nodes() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        struct Counted(Rc<Cell<usize>>);
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let dropped = Rc::new(Cell::new(0));
+        let mut tree = Tree::new();
+        for i in [5, 3, 65, 123, 6, 11, 1, 42] {
+            tree.insert(i, Counted(dropped.clone()));
+        }
+
+        let mut iter = tree.into_iter();
+        for _ in 0..4 {
+            iter.next();
+        }
+        assert_eq!(dropped.get(), 4);
+
+        drop(iter);
+        assert_eq!(dropped.get(), 8);
+    }
+}
@@ -0,0 +1,182 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Array layout of the tree, like in a binary heap: the root is stored
+//! at index 0, children of the node at index `i` are stored at indices
+//! `2i + 1` and `2i + 2`, and slots of missing nodes are None.
+//!
+//! The array has `2^h - 1` slots for a tree of height `h`, so the layout
+//! fits balanced trees only.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::{LevelArrayError, Node, Tree};
+
+/**
+ * Largest height of a tree, which is copied into the array, so the array
+ * has at most `2^24 - 1` slots.
+ */
+const MAX_HEIGHT: usize = 24;
+
+impl<K, V> Tree<K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    /**
+     * Copy entries into the array in level order, with None in slots
+     * of missing nodes. Fails with `LevelArrayError::TooHigh`, when
+     * the tree is higher than 24 levels, e.g. a chain after sorted inserts.
+     */
+    pub fn to_level_array(&self) -> Result<Vec<Option<(K, V)>>, LevelArrayError> {
+        let height = self.height();
+        if height > MAX_HEIGHT {
+            return Err(LevelArrayError::TooHigh { height });
+        }
+        let slots = (1 << height) - 1;
+        let mut array = Vec::new();
+        array.resize_with(slots, || None);
+
+        let mut stack: Vec<(&Node<K, V>, usize)> = self
+            .root
+            .as_deref()
+            .map(|root| (root, 0))
+            .into_iter()
+            .collect();
+        while let Some((node, index)) = stack.pop() {
+            array[index] = Some((node.key.clone(), node.value.clone()));
+            stack.extend(node.left.as_deref().map(|left| (left, 2 * index + 1)));
+            stack.extend(node.right.as_deref().map(|right| (right, 2 * index + 2)));
+        }
+        Ok(array)
+    }
+}
+
+impl<K, V> Tree<K, V>
+where
+    K: Ord,
+{
+    /**
+     * Rebuild the tree of exactly the same shape from its level-order
+     * array. Trailing empty slots may be omitted.
+     */
+    pub fn from_level_array(array: Vec<Option<(K, V)>>) -> Result<Tree<K, V>, LevelArrayError> {
+        let mut tree = Tree::new();
+
+        // Nodes by their index in the array. Children have greater indices
+        // than their parents, so parents are created first.
+        let mut nodes: Vec<Option<*mut Node<K, V>>> = Vec::with_capacity(array.len());
+        for (index, entry) in array.into_iter().enumerate() {
+            let Some((key, value)) = entry else {
+                nodes.push(None);
+                continue;
+            };
+
+            let node_ptr: *mut Node<K, V> = if index == 0 {
+                &mut **tree.root.insert(Box::new(Node::new_root(key, value)))
+            } else {
+                let parent_ptr = nodes[(index - 1) / 2].ok_or(LevelArrayError::Orphan { index })?;
+                // The parent is owned by the tree, which is not borrowed.
+                let parent = unsafe { &mut *parent_ptr };
+                let slot = match index % 2 {
+                    1 => &mut parent.left,
+                    _ => &mut parent.right,
+                };
+                &mut **slot.insert(Box::new(Node::new_leaf(key, value, parent_ptr)))
+            };
+            nodes.push(Some(node_ptr));
+            tree.len += 1;
+        }
+
+        // Sizes are fixed bottom-up, from the greatest index down.
+        for &node_ptr in nodes.iter().rev().flatten() {
+            let node = unsafe { &mut *node_ptr };
+            node.size = 1 + Node::subtree_size(&node.left) + Node::subtree_size(&node.right);
+        }
+
+        if !tree.is_valid_bst() {
+            return Err(LevelArrayError::NotOrdered);
+        }
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{LevelArrayError, Tree};
+
+    #[test]
+    fn level_array_round_trip_keeps_shape() {
+        let mut tree = Tree::new();
+        for i in [5, 3, 65, 1, 6, 123] {
+            tree.insert(i, i * 10);
+        }
+
+        let array = tree.to_level_array().unwrap();
+        let keys: Vec<Option<i32>> = array.iter().map(|entry| entry.map(|(k, _)| k)).collect();
+        assert_eq!(
+            keys,
+            [
+                Some(5),
+                Some(3),
+                Some(65),
+                Some(1),
+                None,
+                Some(6),
+                Some(123)
+            ]
+        );
+
+        let decoded = Tree::from_level_array(array).unwrap();
+        assert!(decoded.structurally_equal(&tree));
+        assert_eq!(decoded, tree);
+        assert_eq!(decoded.len(), 6);
+        assert_eq!(decoded.successor(&3), Some(&5));
+        assert_eq!(decoded.select(4), Some((&65, &650)));
+    }
+
+    #[test]
+    fn level_array_of_demo_tree() {
+        let mut tree = Tree::new();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i);
+        }
+
+        let array = tree.to_level_array().unwrap();
+        assert_eq!(array.len(), 31);
+        assert_eq!(array.iter().flatten().count(), 8);
+        assert!(Tree::from_level_array(array)
+            .unwrap()
+            .structurally_equal(&tree));
+    }
+
+    #[test]
+    fn from_level_array_rejects_invalid_arrays() {
+        let empty: Tree<i32, i32> = Tree::from_level_array(Vec::new()).unwrap();
+        assert!(empty.is_empty());
+        assert!(empty.to_level_array().unwrap().is_empty());
+
+        assert_eq!(
+            Tree::from_level_array(vec![Some((5, 5)), None, None, Some((1, 1))]),
+            Err(LevelArrayError::Orphan { index: 3 })
+        );
+        assert_eq!(
+            Tree::from_level_array(vec![Some((5, 5)), Some((7, 7))]),
+            Err(LevelArrayError::NotOrdered)
+        );
+    }
+
+    #[test]
+    fn too_high_tree_is_rejected() {
+        let chain: Tree<i32, i32> = (0..64).map(|i| (i, i)).collect();
+        assert_eq!(
+            chain.to_level_array(),
+            Err(LevelArrayError::TooHigh { height: 64 })
+        );
+
+        let mut tree: Tree<i32, i32> = (0..25).map(|i| (i, i)).collect();
+        assert!(tree.to_level_array().is_err());
+        tree.rebalance();
+        assert_eq!(tree.to_level_array().unwrap().len(), 31);
+    }
+}
@@ -0,0 +1,4735 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Basic binary search tree implemented in Rust as example.
+//!
+//! The crate needs only `alloc`, when the default `std` feature is disabled.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::iter::successors;
+use core::ops::{Add, Index};
+use core::ptr;
+
+mod arena;
+mod avl;
+mod binary_node;
+mod comparator;
+mod cursor;
+mod depth_limited;
+mod display;
+mod entry;
+mod error;
+mod float;
+mod iter;
+mod level_array;
+mod logged;
+mod mem_size;
+mod multi;
+mod ord_tree;
+mod persistent;
+mod preorder;
+mod scapegoat;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod splay;
+#[cfg(feature = "std")]
+mod sync;
+mod threaded;
+mod treap;
+mod weight_balanced;
+
+pub use arena::ArenaTree;
+pub use avl::AvlTree;
+pub use comparator::{ComparatorTree, DescendingTree};
+pub use cursor::Cursor;
+pub use depth_limited::DepthLimitedTree;
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+pub use error::{DuplicateKey, InsertError, LevelArrayError, ParseError, SortedError};
+pub use float::FloatKey;
+pub use iter::{
+    IntoIter, Iter, IterMut, IterWithDepth, LevelOrder, Postorder, Preorder, Range, RangeMut,
+};
+pub use logged::LoggedTree;
+pub use mem_size::MemSize;
+pub use multi::MultiTree;
+pub use ord_tree::OrdTree;
+pub use persistent::PersistentTree;
+pub use scapegoat::ScapegoatTree;
+pub use splay::SplayTree;
+#[cfg(feature = "std")]
+pub use sync::SyncTree;
+pub use threaded::ThreadedTree;
+pub use treap::Treap;
+pub use weight_balanced::WeightBalancedTree;
+
+/**
+ * Build a tree from `key => value` pairs.
+ *
+ * ```
+ * use basic_binary_tree::tree;
+ *
+ * let tree = tree! { 5 => "a", 3 => "b", 6 => "c", };
+ * assert_eq!(tree.get(&3), Some(&"b"));
+ * assert_eq!(tree.len(), 3);
+ *
+ * let empty: basic_binary_tree::Tree<i32, &str> = tree! {};
+ * assert!(empty.is_empty());
+ * ```
+ */
+#[macro_export]
+macro_rules! tree {
+    ($($key:expr => $value:expr),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut tree = $crate::Tree::new();
+        $(
+            tree.insert($key, $value);
+        )*
+        tree
+    }};
+}
+
+/**
+ * Node of the tree. Fields are private, so a node can be inspected
+ * but cannot be modified in a way that breaks the tree.
+ */
+#[derive(Debug)]
+pub struct Node<K, V> {
+    key: K,
+    value: V,
+
+    left: Option<Box<Node<K, V>>>,
+    right: Option<Box<Node<K, V>>>,
+    parent: Option<*mut Node<K, V>>,
+
+    // Number of nodes in the subtree rooted at this node.
+    size: usize,
+}
+
+/*
+ * Safety: a parent pointer always points to a node of the same tree, or
+ * of the same detached subtree, which owns the node through its children,
+ * so moving the owner to another thread moves all pointed nodes too.
+ * Nodes are mutated through parent pointers only by methods, which borrow
+ * the owning tree exclusively, and shared borrows only read them, so
+ * a node shared between threads is never mutated.
+ */
+unsafe impl<K: Send, V: Send> Send for Node<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Node<K, V> {}
+
+impl<K, V> Node<K, V> {
+    fn new_root(key: K, value: V) -> Self {
+        Self {
+            key,
+            value,
+            left: None,
+            right: None,
+            parent: None,
+            size: 1,
+        }
+    }
+
+    fn new_leaf(key: K, value: V, parent: *mut Node<K, V>) -> Self {
+        Self {
+            key,
+            value,
+            left: None,
+            right: None,
+            parent: Some(parent),
+            size: 1,
+        }
+    }
+
+    /**
+     * Key of the node.
+     */
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /**
+     * Value of the node.
+     */
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+
+    /**
+     * Parent of the node, or None for the root.
+     *
+     * Safety: every node of a tree is owned by the box in its parent, so
+     * its address is stable while it stays in the tree, and all operations,
+     * which move a node to another place, update the parent pointer of the
+     * moved node. Roots of the tree and of detached subtrees have no parent.
+     * A node cannot be freed while its children are reachable through
+     * a shared borrow of the tree, so the pointer is valid for the lifetime
+     * of `&self`. In debug builds the parent is checked to own the node.
+     */
+    fn parent_node(&self) -> Option<&Node<K, V>> {
+        let parent = unsafe { &*self.parent? };
+        debug_assert!(
+            [parent.left.as_deref(), parent.right.as_deref()]
+                .into_iter()
+                .flatten()
+                .any(|child| core::ptr::eq(child, self)),
+            "parent pointer does not point to the owner of the node"
+        );
+        Some(parent)
+    }
+
+    /**
+     * Number of nodes in the subtree, zero for an empty subtree.
+     */
+    fn subtree_size(subtree: &Option<Box<Node<K, V>>>) -> usize {
+        subtree.as_ref().map_or(0, |node| node.size)
+    }
+
+    /**
+     * Recalculate subtree sizes of the node and all its ancestors after
+     * a change in the subtree. The node must belong to an exclusively
+     * borrowed tree, see `parent_node` for the validity of parent pointers.
+     */
+    fn fix_sizes_upwards(mut node: Option<*mut Node<K, V>>) {
+        while let Some(node_ptr) = node {
+            let current_node = unsafe { &mut *node_ptr };
+            current_node.size = 1
+                + Self::subtree_size(&current_node.left)
+                + Self::subtree_size(&current_node.right);
+            node = current_node.parent;
+        }
+    }
+
+    fn leftmost(&self) -> &Node<K, V> {
+        let mut node = self;
+        while let Some(left_node) = node.left.as_deref() {
+            node = left_node;
+        }
+        node
+    }
+
+    fn rightmost(&self) -> &Node<K, V> {
+        let mut node = self;
+        while let Some(right_node) = node.right.as_deref() {
+            node = right_node;
+        }
+        node
+    }
+
+    /**
+     * Next node in key order: leftmost node of the right subtree, or
+     * the first ancestor, which is reached by moving up from a left child.
+     */
+    fn next(&self) -> Option<&Node<K, V>> {
+        if let Some(right_node) = self.right.as_deref() {
+            return Some(right_node.leftmost());
+        }
+
+        let mut child = self;
+        while let Some(parent) = child.parent_node() {
+            if parent
+                .left
+                .as_deref()
+                .is_some_and(|left| core::ptr::eq(left, child))
+            {
+                return Some(parent);
+            }
+            child = parent;
+        }
+        None
+    }
+
+    /**
+     * Previous node in key order, mirror of `next`.
+     */
+    fn prev(&self) -> Option<&Node<K, V>> {
+        if let Some(left_node) = self.left.as_deref() {
+            return Some(left_node.rightmost());
+        }
+
+        let mut child = self;
+        while let Some(parent) = child.parent_node() {
+            if parent
+                .right
+                .as_deref()
+                .is_some_and(|right| core::ptr::eq(right, child))
+            {
+                return Some(parent);
+            }
+            child = parent;
+        }
+        None
+    }
+}
+
+/**
+ * Mutable view of a node. Only the value can be changed, the key and
+ * links to children stay read-only, so the tree remains ordered.
+ * The view borrows the whole tree exclusively while it is alive.
+ */
+#[derive(Debug)]
+pub struct NodeMut<'a, K, V> {
+    node: &'a mut Node<K, V>,
+}
+
+impl<'a, K, V> NodeMut<'a, K, V> {
+    /**
+     * Key of the node.
+     */
+    pub fn key(&self) -> &K {
+        &self.node.key
+    }
+
+    /**
+     * Value of the node.
+     */
+    pub fn value(&self) -> &V {
+        &self.node.value
+    }
+
+    /**
+     * Mutable reference to the value of the node.
+     */
+    pub fn value_mut(&mut self) -> &mut V {
+        &mut self.node.value
+    }
+
+    /**
+     * Convert the view into a mutable reference to the value with
+     * the lifetime of the borrow of the tree.
+     */
+    pub fn into_value_mut(self) -> &'a mut V {
+        &mut self.node.value
+    }
+
+    /**
+     * Key of the left child, if any.
+     */
+    pub fn left_key(&self) -> Option<&K> {
+        self.node.left.as_deref().map(|node| &node.key)
+    }
+
+    /**
+     * Key of the right child, if any.
+     */
+    pub fn right_key(&self) -> Option<&K> {
+        self.node.right.as_deref().map(|node| &node.key)
+    }
+}
+
+/**
+ * Binary search tree, which maps keys to values.
+ *
+ * Keys must be totally ordered, so types like `f64`, where some values
+ * are incomparable, cannot be used as keys directly, use `FloatKey`:
+ *
+ * ```compile_fail
+ * let mut tree = basic_binary_tree::Tree::new();
+ * tree.insert(f64::NAN, ());
+ * ```
+ */
+pub struct Tree<K, V> {
+    root: Option<Box<Node<K, V>>>,
+    len: usize,
+
+    // Number of inserts, which found the key already present.
+    duplicates_rejected: usize,
+}
+
+impl<K, V> Tree<K, V> {
+    /**
+     * Iterate over references to keys and values in ascending key order.
+     */
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter::new(self.root.as_deref())
+    }
+
+    /**
+     * Iterate over references to keys and mutable references to values
+     * in ascending key order.
+     */
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut::new(self.root.as_deref_mut())
+    }
+
+    /**
+     * Iterate over entries in ascending key order together with depths
+     * of their nodes, where the root has depth 0.
+     */
+    pub fn iter_with_depth(&self) -> IterWithDepth<'_, K, V> {
+        IterWithDepth::new(self.root.as_deref())
+    }
+
+    /**
+     * Iterate over entries in pre-order: root, left subtree, right subtree.
+     */
+    pub fn preorder(&self) -> Preorder<'_, K, V> {
+        Preorder::new(self.root.as_deref())
+    }
+
+    /**
+     * Iterate over entries in post-order: left subtree, right subtree, root.
+     */
+    pub fn postorder(&self) -> Postorder<'_, K, V> {
+        Postorder::new(self.root.as_deref())
+    }
+
+    /**
+     * Iterate over entries level by level, from left to right.
+     */
+    pub fn level_order(&self) -> LevelOrder<'_, K, V> {
+        LevelOrder::new(self.root.as_deref())
+    }
+
+    /**
+     * Consume the tree and return its entries in ascending key order.
+     */
+    pub fn into_sorted_vec(self) -> Vec<(K, V)> {
+        let mut entries = Vec::with_capacity(self.len);
+        entries.extend(self);
+        entries
+    }
+
+    /**
+     * Iterate over keys in ascending order.
+     */
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /**
+     * Iterate over values in ascending key order.
+     */
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    /**
+     * Iterate over mutable references to values in ascending key order.
+     */
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+
+    /**
+     * Iterate over entries in ascending value order, entries with equal
+     * values in ascending key order. Unlike `iter`, entries are collected
+     * and sorted first, which takes O(n log n) time and O(n) memory.
+     */
+    pub fn iter_by_value(&self) -> impl Iterator<Item = (&K, &V)>
+    where
+        V: Ord,
+    {
+        let mut entries: Vec<(&K, &V)> = self.iter().collect();
+        // The sort is stable, so the key order of equal values is kept.
+        entries.sort_by_key(|(_, value)| *value);
+        entries.into_iter()
+    }
+
+    /**
+     * Number of key-value pairs in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Number of `insert` and `try_insert` calls, which found the key
+     * already present in the tree.
+     */
+    pub fn duplicates_rejected(&self) -> usize {
+        self.duplicates_rejected
+    }
+
+    /**
+     * Entry with the smallest key.
+     */
+    pub fn min(&self) -> Option<(&K, &V)> {
+        let node = self.root.as_deref()?.leftmost();
+        Some((&node.key, &node.value))
+    }
+
+    /**
+     * Entry with the largest key.
+     */
+    pub fn max(&self) -> Option<(&K, &V)> {
+        let node = self.root.as_deref()?.rightmost();
+        Some((&node.key, &node.value))
+    }
+
+    /**
+     * Entry with the smallest key, same as `min`, named like in `BTreeMap`.
+     *
+     * ```
+     * use basic_binary_tree::Tree;
+     *
+     * let tree: Tree<i32, i32> = [5, 3, 65, 123, 6, 11, 3, 1, 5, 42]
+     *     .into_iter()
+     *     .map(|i| (i, i))
+     *     .collect();
+     * assert_eq!(tree.first_key_value(), Some((&1, &1)));
+     * assert_eq!(tree.last_key_value(), Some((&123, &123)));
+     * ```
+     */
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.min()
+    }
+
+    /**
+     * Entry with the largest key, same as `max`, named like in `BTreeMap`.
+     */
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.max()
+    }
+
+    /**
+     * Entry with the smallest key, with mutable access to its value.
+     */
+    pub fn first_entry(&mut self) -> Option<(&K, &mut V)> {
+        let mut node = self.root.as_deref_mut()?;
+        while node.left.is_some() {
+            node = node.left.as_deref_mut()?;
+        }
+        Some((&node.key, &mut node.value))
+    }
+
+    /**
+     * Entry with the largest key, with mutable access to its value.
+     */
+    pub fn last_entry(&mut self) -> Option<(&K, &mut V)> {
+        let mut node = self.root.as_deref_mut()?;
+        while node.right.is_some() {
+            node = node.right.as_deref_mut()?;
+        }
+        Some((&node.key, &mut node.value))
+    }
+
+    /**
+     * Number of nodes on the longest path from the root to a leaf.
+     */
+    pub fn height(&self) -> usize {
+        self.fold_subtrees(0, |_, left, right| 1 + left.max(right))
+    }
+
+    /**
+     * Keys on the path from the root down through left children, i.e.
+     * from the root to the smallest key. A degenerate tree has all its
+     * keys on one of its spines.
+     */
+    pub fn left_spine(&self) -> Vec<&K> {
+        successors(self.root.as_deref(), |node| node.left.as_deref())
+            .map(|node| &node.key)
+            .collect()
+    }
+
+    /**
+     * Keys on the path from the root down through right children, i.e.
+     * from the root to the largest key.
+     */
+    pub fn right_spine(&self) -> Vec<&K> {
+        successors(self.root.as_deref(), |node| node.right.as_deref())
+            .map(|node| &node.key)
+            .collect()
+    }
+
+    /**
+     * Number of nodes on the longest path between any two nodes, which
+     * may not pass through the root.
+     */
+    pub fn diameter(&self) -> usize {
+        // Height and diameter of each subtree.
+        let (_, diameter) = self.fold_subtrees((0, 0), |_, left, right| {
+            let through_node = 1 + left.0 + right.0;
+            (
+                1 + left.0.max(right.0),
+                through_node.max(left.1).max(right.1),
+            )
+        });
+        diameter
+    }
+
+    /**
+     * Number of nodes on the shortest path from the root to a leaf.
+     * Levels are searched from the root down to the first leaf.
+     */
+    pub fn min_depth(&self) -> usize {
+        let mut queue: VecDeque<(&Node<K, V>, usize)> = self
+            .root
+            .as_deref()
+            .map(|root| (root, 1))
+            .into_iter()
+            .collect();
+        while let Some((node, depth)) = queue.pop_front() {
+            if node.left.is_none() && node.right.is_none() {
+                return depth;
+            }
+            queue.extend(node.left.as_deref().map(|left| (left, depth + 1)));
+            queue.extend(node.right.as_deref().map(|right| (right, depth + 1)));
+        }
+        0
+    }
+
+    /**
+     * Check, that heights of subtrees of every node differ by one at most.
+     */
+    pub fn is_balanced(&self) -> bool {
+        self.fold_subtrees(Some(0usize), |_, left, right| match (left, right) {
+            (Some(left), Some(right)) if left.abs_diff(right) <= 1 => Some(1 + left.max(right)),
+            _ => None,
+        })
+        .is_some()
+    }
+
+    /**
+     * Copy of the tree of the same shape, with values mapped by the function.
+     * Parent pointers of the copied nodes point to their new parents, so
+     * the copy is independent of the original tree.
+     */
+    pub fn map_values<W, F: FnMut(&V) -> W>(&self, mut f: F) -> Tree<K, W>
+    where
+        K: Clone,
+    {
+        let mut tree = Tree::default();
+        let Some(source_root) = self.root.as_deref() else {
+            return tree;
+        };
+
+        let target_root = tree.root.insert(Box::new(Node::new_root(
+            source_root.key.clone(),
+            f(&source_root.value),
+        )));
+        target_root.size = source_root.size;
+        let mut stack = vec![(source_root, &mut **target_root as *mut Node<K, W>)];
+        while let Some((source, target)) = stack.pop() {
+            // Each copied node is pushed to the stack once, so no other
+            // reference to it exists while it's modified.
+            let target = unsafe { &mut *target };
+
+            if let Some(source_left) = source.left.as_deref() {
+                let new_node =
+                    Node::new_leaf(source_left.key.clone(), f(&source_left.value), target);
+                let target_left = target.left.insert(Box::new(new_node));
+                target_left.size = source_left.size;
+                stack.push((source_left, &mut **target_left));
+            }
+
+            if let Some(source_right) = source.right.as_deref() {
+                let new_node =
+                    Node::new_leaf(source_right.key.clone(), f(&source_right.value), target);
+                let target_right = target.right.insert(Box::new(new_node));
+                target_right.size = source_right.size;
+                stack.push((source_right, &mut **target_right));
+            }
+        }
+
+        tree.len = self.len;
+        tree
+    }
+
+    /**
+     * Fold entries in ascending key order.
+     */
+    pub fn fold<B, F: FnMut(B, &K, &V) -> B>(&self, init: B, mut f: F) -> B {
+        self.iter()
+            .fold(init, |acc, (key, value)| f(acc, key, value))
+    }
+
+    /**
+     * Sum of all values, the default value for an empty tree.
+     */
+    pub fn sum_values(&self) -> V
+    where
+        V: Default + Clone + Add<Output = V>,
+    {
+        self.fold(V::default(), |sum, _, value| sum + value.clone())
+    }
+
+    /**
+     * Smallest key, which maps to the value. Values are not ordered,
+     * so all entries are scanned in key order, it takes O(n) time.
+     */
+    pub fn find_by_value(&self, value: &V) -> Option<&K>
+    where
+        V: PartialEq,
+    {
+        self.iter()
+            .find(|(_, current)| *current == value)
+            .map(|(key, _)| key)
+    }
+
+    /**
+     * Number of entries, for which the predicate returns true.
+     */
+    pub fn count_matching<F: FnMut(&K, &V) -> bool>(&self, mut f: F) -> usize {
+        self.iter().filter(|(key, value)| f(key, value)).count()
+    }
+
+    /**
+     * Number of nodes without children.
+     */
+    pub fn count_leaves(&self) -> usize {
+        self.fold_subtrees(0, |node, left, right| {
+            let is_leaf = node.left.is_none() && node.right.is_none();
+            left + right + usize::from(is_leaf)
+        })
+    }
+
+    /**
+     * Iterate over entries of nodes without children in ascending key
+     * order, stepping from one node to the next one.
+     */
+    pub fn iter_leaves(&self) -> impl Iterator<Item = (&K, &V)> {
+        let first = self.root.as_deref().map(Node::leftmost);
+        successors(first, |node| node.next())
+            .filter(|node| node.left.is_none() && node.right.is_none())
+            .map(|node| (&node.key, &node.value))
+    }
+
+    /**
+     * Number of nodes with at least one child.
+     */
+    pub fn count_internal(&self) -> usize {
+        self.fold_subtrees(0, |node, left, right| {
+            let is_internal = node.left.is_some() || node.right.is_some();
+            left + right + usize::from(is_internal)
+        })
+    }
+
+    /**
+     * Fold the tree bottom-up without recursion. The closure receives a node
+     * and results for its left and right subtrees, an empty subtree
+     * produces `empty`.
+     */
+    fn fold_subtrees<T: Clone>(&self, empty: T, mut f: impl FnMut(&Node<K, V>, T, T) -> T) -> T {
+        let mut results = Vec::new();
+        let mut stack = vec![(self.root.as_deref(), false)];
+        while let Some(entry) = stack.pop() {
+            match entry {
+                (None, _) => results.push(empty.clone()),
+
+                (Some(node), false) => {
+                    stack.push((Some(node), true));
+                    stack.push((node.right.as_deref(), false));
+                    stack.push((node.left.as_deref(), false));
+                }
+
+                (Some(node), true) => {
+                    let right = results.pop().expect("result of right subtree");
+                    let left = results.pop().expect("result of left subtree");
+                    results.push(f(node, left, right));
+                }
+            }
+        }
+        results.pop().unwrap_or(empty)
+    }
+
+    /**
+     * Rebuild the tree into a height-balanced shape. Entries are taken out
+     * in key order and the median of each range becomes the root of its
+     * subtree, so the rebuild takes O(n) time.
+     */
+    pub fn rebalance(&mut self) {
+        let mut entries = IntoIter::new(self.root.take());
+        self.root = Self::build_balanced(&mut entries, self.len);
+    }
+
+    /**
+     * Rebuild the tree into a balanced one like `rebalance`, but free all
+     * old nodes first, so new nodes are allocated one after another
+     * in key order and tend to be close to each other in memory.
+     */
+    pub fn compact(&mut self) {
+        let entries: Vec<(K, V)> = IntoIter::new(self.root.take()).collect();
+        self.root = Self::build_balanced(&mut entries.into_iter(), self.len);
+    }
+
+    /**
+     * Rebuild the tree like `rebalance`, only when it is higher than twice
+     * the height of a balanced tree of the same length. Returns true,
+     * when the tree was rebuilt.
+     */
+    pub fn shrink_to_balanced(&mut self) -> bool {
+        // Bit length of len is ceil(log2(len + 1)), the balanced height.
+        let balanced_height = (usize::BITS - self.len.leading_zeros()) as usize;
+        if self.height() <= 2 * balanced_height {
+            return false;
+        }
+        self.rebalance();
+        true
+    }
+
+    /**
+     * Hash of the shape of the tree, which does not depend on keys
+     * and values. Presence of each node and each missing child is fed
+     * in pre-order into the 64-bit FNV-1a hash, so the signature is
+     * the same in every run and on every platform.
+     */
+    pub fn shape_signature(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+        let mut hash = FNV_OFFSET_BASIS;
+        let mut stack = vec![self.root.as_deref()];
+        while let Some(subtree) = stack.pop() {
+            let marker = match subtree {
+                Some(node) => {
+                    stack.push(node.right.as_deref());
+                    stack.push(node.left.as_deref());
+                    b'N'
+                }
+                None => b'#',
+            };
+            hash = (hash ^ u64::from(marker)).wrapping_mul(FNV_PRIME);
+        }
+        hash
+    }
+
+    /**
+     * Check, that both trees have the same shape and the same keys
+     * at the same positions. Values are not compared.
+     */
+    pub fn structurally_equal(&self, other: &Tree<K, V>) -> bool
+    where
+        K: PartialEq,
+    {
+        Self::subtrees_match(
+            self.root.as_deref(),
+            other.root.as_deref(),
+            |node, other_node| node.key == other_node.key,
+        )
+    }
+
+    /**
+     * Check, that some node of the tree is the root of a subtree, which
+     * has the same shape, keys and values as the pattern. Every node is
+     * tried, because keys are only compared for equality.
+     */
+    pub fn contains_subtree(&self, pattern: &Tree<K, V>) -> bool
+    where
+        K: PartialEq,
+        V: PartialEq,
+    {
+        let Some(pattern_root) = pattern.root.as_deref() else {
+            return true;
+        };
+
+        let mut stack: Vec<&Node<K, V>> = self.root.as_deref().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let matches =
+                Self::subtrees_match(Some(node), Some(pattern_root), |node, other_node| {
+                    node.key == other_node.key && node.value == other_node.value
+                });
+            if matches {
+                return true;
+            }
+            stack.extend(node.left.as_deref());
+            stack.extend(node.right.as_deref());
+        }
+        false
+    }
+
+    /**
+     * Check, that both subtrees have the same shape and the closure
+     * accepts each pair of nodes at the same positions.
+     */
+    fn subtrees_match(
+        subtree: Option<&Node<K, V>>,
+        other: Option<&Node<K, V>>,
+        same: impl Fn(&Node<K, V>, &Node<K, V>) -> bool,
+    ) -> bool {
+        let mut stack = vec![(subtree, other)];
+        while let Some(pair) = stack.pop() {
+            match pair {
+                (None, None) => {}
+
+                (Some(node), Some(other_node)) if same(node, other_node) => {
+                    stack.push((node.left.as_deref(), other_node.left.as_deref()));
+                    stack.push((node.right.as_deref(), other_node.right.as_deref()));
+                }
+
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /**
+     * Mirror the tree by swapping children of every node, so entries are
+     * iterated in descending key order. Lookups rely on ascending order,
+     * so they do not work, until the tree is inverted back.
+     * Parent pointers and sizes are not affected by the swap.
+     */
+    pub fn invert(&mut self) {
+        let mut stack: Vec<&mut Node<K, V>> = self.root.as_deref_mut().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            core::mem::swap(&mut node.left, &mut node.right);
+            stack.extend(node.left.as_deref_mut());
+            stack.extend(node.right.as_deref_mut());
+        }
+    }
+
+    /**
+     * Keep only entries, for which the predicate returns true, and return
+     * the number of removed entries. Surviving entries are rebuilt into
+     * a balanced tree.
+     */
+    pub fn retain<F: FnMut(&K, &V) -> bool>(&mut self, mut f: F) -> usize {
+        let entries: Vec<(K, V)> = IntoIter::new(self.root.take())
+            .filter(|(key, value)| f(key, value))
+            .collect();
+        let removed = self.len - entries.len();
+        self.len = entries.len();
+        self.root = Self::build_balanced(&mut entries.into_iter(), self.len);
+        removed
+    }
+
+    /**
+     * Remove entries, whose value is equal to the value of the previous
+     * entry in key order, so only the first entry of each run of equal
+     * values is kept. Returns the number of removed entries. Surviving
+     * entries are rebuilt into a balanced tree.
+     */
+    pub fn dedup_values(&mut self) -> usize
+    where
+        V: PartialEq,
+    {
+        let mut entries: Vec<(K, V)> = IntoIter::new(self.root.take()).collect();
+        entries.dedup_by(|(_, value), (_, previous)| value == previous);
+        let removed = self.len - entries.len();
+        self.len = entries.len();
+        self.root = Self::build_balanced(&mut entries.into_iter(), self.len);
+        removed
+    }
+
+    /**
+     * Remove entries, for which the predicate returns true, and return
+     * them in key order. Remaining entries are rebuilt into a balanced tree.
+     */
+    pub fn extract_if<F: FnMut(&K, &V) -> bool>(&mut self, mut f: F) -> Vec<(K, V)> {
+        let (extracted, retained): (Vec<_>, Vec<_>) =
+            IntoIter::new(self.root.take()).partition(|(key, value)| f(key, value));
+        self.len = retained.len();
+        self.root = Self::build_balanced(&mut retained.into_iter(), self.len);
+        extracted
+    }
+
+    /**
+     * Build a balanced subtree from the next `len` entries of an iterator,
+     * which yields entries in ascending key order. The left subtree is
+     * built first, so entries are consumed in order, and parent pointers
+     * of both subtrees are set, when their parent node is created.
+     */
+    fn build_balanced<I>(entries: &mut I, len: usize) -> Option<Box<Node<K, V>>>
+    where
+        I: Iterator<Item = (K, V)>,
+    {
+        if len == 0 {
+            return None;
+        }
+
+        let left_len = len / 2;
+        let left = Self::build_balanced(entries, left_len);
+        let (key, value) = entries.next()?;
+
+        let mut node = Box::new(Node::new_root(key, value));
+        let node_ptr: *mut Node<K, V> = &mut *node;
+        Self::splice(&mut node.left, left, Some(node_ptr));
+        let right = Self::build_balanced(entries, len - left_len - 1);
+        Self::splice(&mut node.right, right, Some(node_ptr));
+        node.size = 1 + Node::subtree_size(&node.left) + Node::subtree_size(&node.right);
+        Some(node)
+    }
+
+    /**
+     * Rotate the subtree in the slot to the left: the right child of
+     * the root becomes the new root and the old root becomes its left
+     * child. Returns false, when there is no right child to rotate.
+     */
+    fn rotate_left_at(slot: &mut Option<Box<Node<K, V>>>) -> bool {
+        let Some(mut node) = slot.take() else {
+            return false;
+        };
+        let Some(mut pivot) = node.right.take() else {
+            *slot = Some(node);
+            return false;
+        };
+
+        let node_ptr: *mut Node<K, V> = &mut *node;
+        let pivot_ptr: *mut Node<K, V> = &mut *pivot;
+        let parent = node.parent;
+
+        Self::splice(&mut node.right, pivot.left.take(), Some(node_ptr));
+        node.size = 1 + Node::subtree_size(&node.left) + Node::subtree_size(&node.right);
+        Self::splice(&mut pivot.left, Some(node), Some(pivot_ptr));
+        pivot.size = 1 + Node::subtree_size(&pivot.left) + Node::subtree_size(&pivot.right);
+        Self::splice(slot, Some(pivot), parent);
+        true
+    }
+
+    /**
+     * Rotate the subtree in the slot to the right, mirror of `rotate_left_at`.
+     */
+    fn rotate_right_at(slot: &mut Option<Box<Node<K, V>>>) -> bool {
+        let Some(mut node) = slot.take() else {
+            return false;
+        };
+        let Some(mut pivot) = node.left.take() else {
+            *slot = Some(node);
+            return false;
+        };
+
+        let node_ptr: *mut Node<K, V> = &mut *node;
+        let pivot_ptr: *mut Node<K, V> = &mut *pivot;
+        let parent = node.parent;
+
+        Self::splice(&mut node.left, pivot.right.take(), Some(node_ptr));
+        node.size = 1 + Node::subtree_size(&node.left) + Node::subtree_size(&node.right);
+        Self::splice(&mut pivot.right, Some(node), Some(pivot_ptr));
+        pivot.size = 1 + Node::subtree_size(&pivot.left) + Node::subtree_size(&pivot.right);
+        Self::splice(slot, Some(pivot), parent);
+        true
+    }
+
+    /**
+     * Tree with the given root node of the subtree with `len` nodes.
+     */
+    fn from_root(root: Option<Box<Node<K, V>>>, len: usize) -> Self {
+        Self {
+            root,
+            len,
+            duplicates_rejected: 0,
+        }
+    }
+
+    /**
+     * Put the subtree into the slot and point its root to the new parent.
+     */
+    fn splice(
+        slot: &mut Option<Box<Node<K, V>>>,
+        subtree: Option<Box<Node<K, V>>>,
+        parent: Option<*mut Node<K, V>>,
+    ) {
+        *slot = subtree;
+        if let Some(node) = slot.as_deref_mut() {
+            node.parent = parent;
+        }
+    }
+
+    /**
+     * Remove all entries from the tree.
+     * Nodes are unlinked one by one in a loop, so even a deep degenerate
+     * tree is dropped without recursion.
+     */
+    pub fn clear(&mut self) {
+        let mut stack: Vec<Box<Node<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+        self.len = 0;
+    }
+
+    /**
+     * Walk the tree from the root down and point the parent pointer of
+     * every node to its actual parent. Returns the number of fixed nodes.
+     */
+    pub fn verify_and_repair(&mut self) -> usize {
+        let mut fixed = 0;
+        let mut stack = vec![(self.root.as_deref_mut(), None)];
+        while let Some((subtree, parent)) = stack.pop() {
+            let Some(node) = subtree else {
+                continue;
+            };
+            if node.parent != parent {
+                node.parent = parent;
+                fixed += 1;
+            }
+
+            let node_ptr: *mut Node<K, V> = node;
+            stack.push((node.left.as_deref_mut(), Some(node_ptr)));
+            stack.push((node.right.as_deref_mut(), Some(node_ptr)));
+        }
+        fixed
+    }
+}
+
+impl<K, V> Tree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Build a balanced tree from pairs in O(n) time.
+     * Pairs must be sorted by key in ascending order, without duplicates.
+     */
+    pub fn from_sorted(pairs: Vec<(K, V)>) -> Self {
+        debug_assert!(
+            pairs.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "pairs must be sorted by key and unique"
+        );
+
+        let len = pairs.len();
+        Self::from_root(Self::build_balanced(&mut pairs.into_iter(), len), len)
+    }
+
+    /**
+     * Build a balanced tree from pairs like `from_sorted`, or return
+     * an error for the first pair, which key is not greater than the key
+     * of the previous pair.
+     */
+    pub fn try_from_sorted(pairs: Vec<(K, V)>) -> Result<Self, SortedError<K>> {
+        let Some(index) = pairs.windows(2).position(|pair| pair[0].0 >= pair[1].0) else {
+            return Ok(Self::from_sorted(pairs));
+        };
+
+        let duplicate = pairs[index].0 == pairs[index + 1].0;
+        let (key, _) = pairs.into_iter().nth(index + 1).expect("offending pair");
+        let index = index + 1;
+        Err(match duplicate {
+            true => SortedError::Duplicate { index, key },
+            false => SortedError::OutOfOrder { index, key },
+        })
+    }
+
+    /**
+     * Sort pairs by key and build a balanced tree like `from_sorted`,
+     * or return an error with a key, which occurs more than once.
+     */
+    pub fn from_unsorted_unique(mut pairs: Vec<(K, V)>) -> Result<Self, DuplicateKey<K>> {
+        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Self::try_from_sorted(pairs).map_err(|error| match error {
+            SortedError::Duplicate { key, .. } | SortedError::OutOfOrder { key, .. } => {
+                DuplicateKey(key)
+            }
+        })
+    }
+
+    /**
+     * Build a balanced tree from pairs of an iterator, like `from_sorted`.
+     * The iterator must yield keys in ascending order, without duplicates.
+     */
+    pub fn from_sorted_iter<I>(pairs: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        Self::from_sorted(pairs.into_iter().collect())
+    }
+
+    /**
+     * Move all entries of the other tree into this tree. Like `insert`,
+     * entries of the other tree replace values of keys, which are present
+     * in both trees. Both trees are merged in key order and the result
+     * is rebuilt into a balanced tree in O(n + m) time.
+     */
+    pub fn merge(&mut self, other: Tree<K, V>) {
+        let mut entries = Vec::with_capacity(self.len + other.len);
+        let mut own = IntoIter::new(self.root.take()).peekable();
+        let mut other = other.into_iter().peekable();
+
+        loop {
+            let ordering = match (own.peek(), other.peek()) {
+                (Some((own_key, _)), Some((other_key, _))) => own_key.cmp(other_key),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+
+            match ordering {
+                Ordering::Less => entries.extend(own.next()),
+                Ordering::Greater => entries.extend(other.next()),
+                Ordering::Equal => {
+                    own.next();
+                    entries.extend(other.next());
+                }
+            }
+        }
+
+        self.len = entries.len();
+        self.root = Self::build_balanced(&mut entries.into_iter(), self.len);
+    }
+
+    /**
+     * Move all entries of the other tree, whose keys are all greater than
+     * keys of this tree, to this tree and leave the other tree empty.
+     * The other tree is attached whole as the right child of the largest
+     * node, so its entries are not visited.
+     */
+    pub fn append(&mut self, other: &mut Tree<K, V>) {
+        debug_assert!(
+            self.keys()
+                .next_back()
+                .zip(other.keys().next())
+                .is_none_or(|(last, first)| last < first),
+            "keys of the appended tree must be greater than keys of the tree"
+        );
+        let Some(subtree) = other.root.take() else {
+            return;
+        };
+
+        let mut parent = None;
+        let mut slot = &mut self.root;
+        while let Some(node) = slot {
+            parent = Some(&mut **node as *mut Node<K, V>);
+            slot = &mut node.right;
+        }
+        Self::splice(slot, Some(subtree), parent);
+        Node::fix_sizes_upwards(parent);
+
+        self.len += other.len;
+        other.len = 0;
+    }
+
+    /**
+     * Merge both trees into a new balanced tree in O(n + m) time, like
+     * `merge`, but entries of this tree win for keys present in both.
+     */
+    pub fn merge_balanced(self, mut other: Tree<K, V>) -> Tree<K, V> {
+        other.merge(self);
+        other
+    }
+
+    /**
+     * New tree with entries of keys, which are present in both trees,
+     * with values from this tree. Both trees are walked in key order and
+     * the result is built balanced in O(n + m) time.
+     */
+    pub fn intersection(&self, other: &Tree<K, V>) -> Tree<K, V>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.combine(other, |own, other| own.filter(|_| other.is_some()))
+    }
+
+    /**
+     * New tree with entries of keys from both trees. Values of keys, which
+     * are present in both trees, are taken from this tree, unlike `merge`.
+     * The result is built balanced in O(n + m) time.
+     */
+    pub fn union(&self, other: &Tree<K, V>) -> Tree<K, V>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.combine(other, |own, other| own.or(other))
+    }
+
+    /**
+     * New tree with entries of this tree, which keys are not present
+     * in the other tree. The result is built balanced in O(n + m) time.
+     */
+    pub fn difference(&self, other: &Tree<K, V>) -> Tree<K, V>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.combine(other, |own, other| own.filter(|_| other.is_none()))
+    }
+
+    /**
+     * New tree with entries of keys, which are present in exactly one
+     * of the trees. The result is built balanced in O(n + m) time.
+     */
+    pub fn symmetric_difference(&self, other: &Tree<K, V>) -> Tree<K, V>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.combine(other, |own, other| match (own, other) {
+            (Some(_), Some(_)) => None,
+            (own, other) => own.or(other),
+        })
+    }
+
+    /**
+     * Walk both trees in key order and build a balanced tree from copies
+     * of entries chosen by the function. The function gets entries of
+     * the next key from both trees, one of them is None, when the key is
+     * present in one tree only.
+     */
+    fn combine<'a, F>(&'a self, other: &'a Tree<K, V>, mut choose: F) -> Tree<K, V>
+    where
+        K: Clone,
+        V: Clone,
+        F: FnMut(Option<(&'a K, &'a V)>, Option<(&'a K, &'a V)>) -> Option<(&'a K, &'a V)>,
+    {
+        let mut entries = Vec::new();
+        let mut own = self.iter().peekable();
+        let mut other = other.iter().peekable();
+
+        loop {
+            let ordering = match (own.peek(), other.peek()) {
+                (Some((own_key, _)), Some((other_key, _))) => own_key.cmp(other_key),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+
+            let chosen = match ordering {
+                Ordering::Less => choose(own.next(), None),
+                Ordering::Greater => choose(None, other.next()),
+                Ordering::Equal => choose(own.next(), other.next()),
+            };
+            entries.extend(chosen.map(|(key, value)| (key.clone(), value.clone())));
+        }
+
+        let len = entries.len();
+        Self::from_root(Self::build_balanced(&mut entries.into_iter(), len), len)
+    }
+
+    /**
+     * Split the tree into two trees: the first one holds keys less than
+     * the pivot and the second one holds the rest of keys. Both trees
+     * are rebuilt balanced.
+     */
+    pub fn split(self, pivot: &K) -> (Tree<K, V>, Tree<K, V>) {
+        let mut less = self.into_sorted_vec();
+        let at = less.partition_point(|(key, _)| key < pivot);
+        let rest = less.split_off(at);
+        (Self::from_sorted(less), Self::from_sorted(rest))
+    }
+
+    /**
+     * Move entries with keys, which are not less than the key, into a new
+     * tree, like `split`, but this tree keeps smaller keys. Both trees
+     * are rebuilt balanced, and this tree keeps its counter of rejected
+     * duplicates.
+     */
+    pub fn split_off(&mut self, key: &K) -> Tree<K, V> {
+        let duplicates_rejected = self.duplicates_rejected;
+        let (less, rest) = core::mem::take(self).split(key);
+        *self = less;
+        self.duplicates_rejected = duplicates_rejected;
+        rest
+    }
+
+    /**
+     * Remove all entries with keys, which are less than the key, and
+     * return their number. Removed nodes are dropped with their whole
+     * left subtrees.
+     */
+    pub fn prune_below(&mut self, key: &K) -> usize {
+        self.prune(key, Ordering::Less)
+    }
+
+    /**
+     * Remove all entries with keys, which are greater than the key, and
+     * return their number. Removed nodes are dropped with their whole
+     * right subtrees.
+     */
+    pub fn prune_above(&mut self, key: &K) -> usize {
+        self.prune(key, Ordering::Greater)
+    }
+
+    /**
+     * Keep only entries with keys in the inclusive range from `low`
+     * to `high` and return the number of removed entries. Subtrees out
+     * of the range are dropped whole, on one walk down along each bound,
+     * like in `prune_below` and `prune_above`.
+     */
+    pub fn retain_range(&mut self, low: &K, high: &K) -> usize {
+        self.prune(low, Ordering::Less) + self.prune(high, Ordering::Greater)
+    }
+
+    /**
+     * Remove all entries with keys in the inclusive range from `low`
+     * to `high` and return their number. The highest node in the range
+     * is detached with its subtrees, entries out of the range are cut
+     * from both subtrees like in `retain_range`, and the rest of the right
+     * subtree is attached under the largest node of the left one.
+     */
+    pub fn remove_range(&mut self, low: &K, high: &K) -> usize {
+        let mut parent = None;
+        let mut slot = &mut self.root;
+        while let Some(node) = slot.as_deref() {
+            let go_left = if node.key < *low {
+                false
+            } else if node.key > *high {
+                true
+            } else {
+                break;
+            };
+
+            let Some(node) = slot.as_deref_mut() else {
+                return 0;
+            };
+            parent = Some(node as *mut Node<K, V>);
+            slot = match go_left {
+                true => &mut node.left,
+                false => &mut node.right,
+            };
+        }
+        let Some(mut top_node) = slot.take() else {
+            return 0;
+        };
+
+        let mut left = Self::subtree_as_tree(top_node.left.take());
+        left.prune(low, Ordering::Greater);
+        left.remove(low);
+        let mut right = Self::subtree_as_tree(top_node.right.take());
+        right.prune(high, Ordering::Less);
+        right.remove(high);
+
+        let removed = top_node.size - left.len - right.len;
+        left.append(&mut right);
+        Self::splice(slot, left.root.take(), parent);
+        Node::fix_sizes_upwards(parent);
+        self.len -= removed;
+        removed
+    }
+
+    /**
+     * Wrap a detached subtree into a tree of its own.
+     */
+    fn subtree_as_tree(mut subtree: Option<Box<Node<K, V>>>) -> Tree<K, V> {
+        if let Some(node) = subtree.as_deref_mut() {
+            node.parent = None;
+        }
+        let len = Node::subtree_size(&subtree);
+        Tree::from_root(subtree, len)
+    }
+
+    /**
+     * Walk down from the root and drop every node, which is on the side
+     * of the key, together with its subtree on that side, replacing it
+     * by its other subtree. Only sizes on the walked path are changed.
+     */
+    fn prune(&mut self, key: &K, side: Ordering) -> usize {
+        let len = self.len;
+        let mut parent = None;
+        let mut slot = &mut self.root;
+        while let Some(node) = slot.as_deref() {
+            if node.key.cmp(key) == side {
+                let Some(mut pruned_node) = slot.take() else {
+                    break;
+                };
+                let kept = match side {
+                    Ordering::Less => pruned_node.right.take(),
+                    _ => pruned_node.left.take(),
+                };
+                drop(IntoIter::new(Some(pruned_node)));
+                Self::splice(slot, kept, parent);
+                continue;
+            }
+
+            let Some(node) = slot.as_deref_mut() else {
+                break;
+            };
+            parent = Some(node as *mut Node<K, V>);
+            slot = match side {
+                Ordering::Less => &mut node.left,
+                _ => &mut node.right,
+            };
+        }
+
+        Node::fix_sizes_upwards(parent);
+        self.len = Node::subtree_size(&self.root);
+        len - self.len
+    }
+
+    /**
+     * Consume the tree and move its entries into a `BTreeMap`.
+     */
+    pub fn to_btree_map(self) -> BTreeMap<K, V> {
+        self.into_iter().collect()
+    }
+
+    /**
+     * Insert key-value pair into tree. When the key is already present,
+     * its value is replaced and the previous value is returned.
+     */
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut parent = None;
+        let mut slot = &mut self.root;
+
+        while let Some(node) = slot {
+            let node_ptr: *mut Node<K, V> = &mut **node;
+            slot = match key.cmp(&node.key) {
+                Ordering::Equal => {
+                    self.duplicates_rejected += 1;
+                    return Some(core::mem::replace(&mut node.value, value));
+                }
+                Ordering::Less => &mut node.left,
+                Ordering::Greater => &mut node.right,
+            };
+            parent = Some(node_ptr);
+        }
+
+        let new_node = match parent {
+            Some(parent_ptr) => Node::new_leaf(key, value, parent_ptr),
+            None => Node::new_root(key, value),
+        };
+        *slot = Some(Box::new(new_node));
+        Node::fix_sizes_upwards(parent);
+        self.len += 1;
+        None
+    }
+
+    /**
+     * Insert key-value pair into tree, only when the key is not present.
+     * Returns false and keeps the existing value otherwise.
+     */
+    pub fn try_insert(&mut self, key: K, value: V) -> bool {
+        match self.entry(key) {
+            Entry::Occupied(_) => {
+                self.duplicates_rejected += 1;
+                false
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                true
+            }
+        }
+    }
+
+    /**
+     * Get mutable reference to the value of the key, inserting the given
+     * value first, when the key is not present. Otherwise the given value
+     * is dropped.
+     */
+    pub fn get_or_insert(&mut self, key: K, default: V) -> &mut V {
+        self.entry(key).or_insert(default)
+    }
+
+    /**
+     * Get mutable reference to the value of the key, inserting the value
+     * returned by the function first, when the key is not present.
+     */
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key: K, f: F) -> &mut V {
+        self.entry(key).or_insert_with(f)
+    }
+
+    /**
+     * Get mutable reference to the value of the key, inserting the default
+     * value first, when the key is not present.
+     */
+    pub fn entry_or_default(&mut self, key: K) -> &mut V
+    where
+        V: Default,
+    {
+        self.get_or_insert_with(key, V::default)
+    }
+
+    /**
+     * Replace the value of an existing key and return the previous value.
+     * When the key is not present, the tree is not changed, the new value
+     * is dropped and None is returned.
+     */
+    pub fn replace_value(&mut self, key: &K, value: V) -> Option<V> {
+        let current = self.get_mut(key)?;
+        Some(core::mem::replace(current, value))
+    }
+
+    /**
+     * Insert all pairs like `insert` and return the number of newly added
+     * keys. Values of keys, which are already present, are replaced.
+     */
+    pub fn insert_many<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) -> usize {
+        iter.into_iter()
+            .map(|(key, value)| self.insert(key, value))
+            .filter(Option::is_none)
+            .count()
+    }
+
+    /**
+     * Remove all listed keys like `remove` and return the number of keys,
+     * which were present. Keys are removed one by one, so the tree keeps
+     * its shape around the removed nodes, and each removal costs O(h).
+     */
+    pub fn remove_many<'a, I>(&mut self, keys: I) -> usize
+    where
+        I: IntoIterator<Item = &'a K>,
+        K: 'a,
+    {
+        keys.into_iter().filter_map(|key| self.remove(key)).count()
+    }
+
+    /**
+     * Find a node in the tree by the key.
+     */
+    pub fn find(&self, key: K) -> Option<&Node<K, V>> {
+        self.find_node(&key)
+    }
+
+    /**
+     * Find a node in the tree by the key, with access to its value
+     * for modification. The tree is borrowed exclusively by the view.
+     */
+    pub fn find_mut(&mut self, key: &K) -> Option<NodeMut<'_, K, V>> {
+        self.find_node_mut(key).map(|node| NodeMut { node })
+    }
+
+    /**
+     * Check is the key present in the tree.
+     */
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_node(key).is_some()
+    }
+
+    /**
+     * Get reference to the value stored under the key.
+     */
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_node(key).map(|node| &node.value)
+    }
+
+    /**
+     * Get references to values of all keys in the order of keys,
+     * with None for missing keys.
+     */
+    pub fn get_many<const N: usize>(&self, keys: [&K; N]) -> [Option<&V>; N] {
+        keys.map(|key| self.get(key))
+    }
+
+    /**
+     * Get mutable references to values of distinct keys at once, in the
+     * order of keys. Returns None, when a key is missing or two keys
+     * are equal.
+     */
+    pub fn get_disjoint_mut<const N: usize>(&mut self, keys: [&K; N]) -> Option<[&mut V; N]> {
+        for (index, key) in keys.iter().enumerate() {
+            if keys[..index].contains(key) {
+                return None;
+            }
+        }
+
+        // Each lookup walks down from the root through raw pointers. No
+        // reference to a whole node is created, only keys are read and
+        // child links are borrowed, so pointers to values, which are taken
+        // by earlier lookups, stay valid.
+        let root = Self::node_ptr(&mut self.root);
+        let mut values = [ptr::null_mut(); N];
+        for (value, key) in values.iter_mut().zip(keys) {
+            let mut subtree = root;
+            *value = loop {
+                let node_ptr = subtree?;
+                subtree = match key.cmp(unsafe { &(*node_ptr).key }) {
+                    Ordering::Equal => break unsafe { ptr::addr_of_mut!((*node_ptr).value) },
+                    Ordering::Less => Self::node_ptr(unsafe { &mut (*node_ptr).left }),
+                    Ordering::Greater => Self::node_ptr(unsafe { &mut (*node_ptr).right }),
+                };
+            };
+        }
+        // Keys are distinct, so each pointer points into another node, and
+        // the tree stays borrowed exclusively, while the references live.
+        Some(values.map(|value| unsafe { &mut *value }))
+    }
+
+    /**
+     * Raw pointer to the node in the link, taken without creating
+     * a reference to the node.
+     */
+    fn node_ptr(link: &mut Option<Box<Node<K, V>>>) -> Option<*mut Node<K, V>> {
+        link.as_mut().map(|node| ptr::addr_of_mut!(**node))
+    }
+
+    /**
+     * Get references to the stored key and its value, which is useful,
+     * when the stored key holds more, than the key used for lookup.
+     */
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_node(key).map(|node| (&node.key, &node.value))
+    }
+
+    /**
+     * Get mutable reference to the value stored under the key.
+     */
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_node_mut(key).map(|node| &mut node.value)
+    }
+
+    /**
+     * Swap values stored under two existing keys. When either key is
+     * missing, nothing is changed and false is returned.
+     */
+    pub fn swap_values(&mut self, a: &K, b: &K) -> bool {
+        if a == b {
+            return self.find_node(a).is_some();
+        }
+        let (low, high) = if a < b { (a, b) } else { (b, a) };
+
+        // Descend, while both keys are on the same side of the node, then
+        // look for them in different parts of the subtree, so mutable
+        // references to both values can be held at once.
+        let mut subtree = self.root.as_deref_mut();
+        while let Some(node) = subtree {
+            if *high < node.key {
+                subtree = node.left.as_deref_mut();
+            } else if *low > node.key {
+                subtree = node.right.as_deref_mut();
+            } else {
+                let (first, second) = if *low == node.key {
+                    (Some(&mut node.value), Self::value_in(&mut node.right, high))
+                } else if *high == node.key {
+                    (Self::value_in(&mut node.left, low), Some(&mut node.value))
+                } else {
+                    (
+                        Self::value_in(&mut node.left, low),
+                        Self::value_in(&mut node.right, high),
+                    )
+                };
+                let (Some(first), Some(second)) = (first, second) else {
+                    return false;
+                };
+                core::mem::swap(first, second);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn value_in<'a>(subtree: &'a mut Option<Box<Node<K, V>>>, key: &K) -> Option<&'a mut V> {
+        Self::find_in_subtree_mut(subtree.as_deref_mut(), key).map(|node| &mut node.value)
+    }
+
+    /**
+     * Key, which follows the given existing key in sorted order.
+     */
+    pub fn successor(&self, key: &K) -> Option<&K> {
+        self.find_node(key)?.next().map(|node| &node.key)
+    }
+
+    /**
+     * Key, which precedes the given existing key in sorted order.
+     */
+    pub fn predecessor(&self, key: &K) -> Option<&K> {
+        self.find_node(key)?.prev().map(|node| &node.key)
+    }
+
+    /**
+     * Entry, which follows the given existing key in sorted order.
+     */
+    pub fn successor_entry(&self, key: &K) -> Option<(&K, &V)> {
+        self.find_node(key)?
+            .next()
+            .map(|node| (&node.key, &node.value))
+    }
+
+    /**
+     * Entry, which precedes the given existing key in sorted order.
+     */
+    pub fn predecessor_entry(&self, key: &K) -> Option<(&K, &V)> {
+        self.find_node(key)?
+            .prev()
+            .map(|node| (&node.key, &node.value))
+    }
+
+    /**
+     * Iterate over entries strictly between two existing keys in ascending
+     * key order, stepping from one node to the next one. Nothing is
+     * yielded, when either key is missing or `start` is not less than `end`.
+     */
+    pub fn between(&self, start: &K, end: &K) -> impl Iterator<Item = (&K, &V)> {
+        let anchors = match (self.find_node(start), self.find_node(end)) {
+            (Some(first), Some(last)) if start < end => Some((first, last)),
+            _ => None,
+        };
+
+        anchors
+            .into_iter()
+            .flat_map(|(first, last)| {
+                successors(first.next(), |node| node.next())
+                    .take_while(move |node| !ptr::eq(*node, last))
+            })
+            .map(|node| (&node.key, &node.value))
+    }
+
+    /**
+     * Iterate over entries with keys in closed interval `[low, high]`
+     * in ascending key order. Subtrees outside of the interval are
+     * not visited.
+     */
+    pub fn range(&self, low: &K, high: &K) -> Range<'_, K, V> {
+        Range::new(self.root.as_deref(), low, high)
+    }
+
+    /**
+     * Iterate over entries with keys in closed interval `[low, high]`
+     * in descending key order, like `range` from its back end.
+     */
+    pub fn range_rev(&self, low: &K, high: &K) -> core::iter::Rev<Range<'_, K, V>> {
+        self.range(low, high).rev()
+    }
+
+    /**
+     * Iterate over entries with keys in closed interval `[low, high]`
+     * with mutable references to values. Keys stay immutable.
+     */
+    pub fn range_mut(&mut self, low: &K, high: &K) -> RangeMut<'_, K, V> {
+        let len = self.count_range(low, high);
+        RangeMut::new(self.root.as_deref_mut(), low, len)
+    }
+
+    /**
+     * Largest key, which is less than or equal to the given key.
+     */
+    pub fn floor(&self, key: &K) -> Option<&K> {
+        let mut best = None;
+        let mut subtree = self.root.as_deref();
+        while let Some(node) = subtree {
+            match node.key.cmp(key) {
+                Ordering::Equal => return Some(&node.key),
+                Ordering::Less => {
+                    best = Some(&node.key);
+                    subtree = node.right.as_deref();
+                }
+                Ordering::Greater => subtree = node.left.as_deref(),
+            }
+        }
+        best
+    }
+
+    /**
+     * Smallest key, which is greater than or equal to the given key.
+     */
+    pub fn ceiling(&self, key: &K) -> Option<&K> {
+        self.lower_bound(key).map(|(key, _)| key)
+    }
+
+    /**
+     * Entry with the smallest key, which is greater than or equal to the
+     * given key, like `lower_bound` of C++ `std::map`.
+     */
+    pub fn lower_bound(&self, key: &K) -> Option<(&K, &V)> {
+        self.first_node_after(key, true)
+            .map(|node| (&node.key, &node.value))
+    }
+
+    /**
+     * Entry with the smallest key, which is strictly greater than the
+     * given key, like `upper_bound` of C++ `std::map`.
+     */
+    pub fn upper_bound(&self, key: &K) -> Option<(&K, &V)> {
+        self.first_node_after(key, false)
+            .map(|node| (&node.key, &node.value))
+    }
+
+    /**
+     * Node with the smallest key greater than the given key, or equal
+     * to it, when `inclusive` is set.
+     */
+    fn first_node_after(&self, key: &K, inclusive: bool) -> Option<&Node<K, V>> {
+        let mut best = None;
+        let mut subtree = self.root.as_deref();
+        while let Some(node) = subtree {
+            match node.key.cmp(key) {
+                Ordering::Equal if inclusive => return Some(node),
+                Ordering::Greater => {
+                    best = Some(node);
+                    subtree = node.left.as_deref();
+                }
+                Ordering::Equal | Ordering::Less => subtree = node.right.as_deref(),
+            }
+        }
+        best
+    }
+
+    /**
+     * Entry with the key nearest to the target by the distance function.
+     * Only the floor and the ceiling of the target are considered, and
+     * the floor wins, when both are at the same distance.
+     */
+    pub fn closest_by<D, M>(&self, target: &K, dist: D) -> Option<(&K, &V)>
+    where
+        D: Fn(&K, &K) -> M,
+        M: Ord,
+    {
+        let key = [self.floor(target), self.ceiling(target)]
+            .into_iter()
+            .flatten()
+            .min_by_key(|key| dist(key, target))?;
+        self.find_node(key).map(|node| (&node.key, &node.value))
+    }
+
+    /**
+     * Entry at the position `n` (starting from zero) in sorted order.
+     * Uses cached subtree sizes, so it takes one walk from the root down.
+     */
+    pub fn select(&self, mut n: usize) -> Option<(&K, &V)> {
+        let mut subtree = self.root.as_deref();
+        while let Some(node) = subtree {
+            let left_size = Node::subtree_size(&node.left);
+            match n.cmp(&left_size) {
+                Ordering::Less => subtree = node.left.as_deref(),
+                Ordering::Equal => return Some((&node.key, &node.value)),
+                Ordering::Greater => {
+                    n -= left_size + 1;
+                    subtree = node.right.as_deref();
+                }
+            }
+        }
+        None
+    }
+
+    /**
+     * Entry at the position `n` (starting from zero) in ascending order,
+     * same as `select`.
+     */
+    pub fn nth(&self, n: usize) -> Option<(&K, &V)> {
+        self.select(n)
+    }
+
+    /**
+     * The k-th smallest entry, where `k = 1` is the entry with the smallest
+     * key. Returns None, when `k` is zero or greater than the length.
+     */
+    pub fn kth_smallest(&self, k: usize) -> Option<(&K, &V)> {
+        self.select(k.checked_sub(1)?)
+    }
+
+    /**
+     * The k-th largest entry, where `k = 1` is the entry with the largest
+     * key. Returns None, when `k` is zero or greater than the length.
+     */
+    pub fn kth_largest(&self, k: usize) -> Option<(&K, &V)> {
+        match k {
+            0 => None,
+            _ => self.select(self.len.checked_sub(k)?),
+        }
+    }
+
+    /**
+     * Number of keys in the tree, which are strictly less than the key.
+     */
+    pub fn rank(&self, key: &K) -> usize {
+        let mut rank = 0;
+        let mut subtree = self.root.as_deref();
+        while let Some(node) = subtree {
+            match key.cmp(&node.key) {
+                Ordering::Less => subtree = node.left.as_deref(),
+                Ordering::Equal => return rank + Node::subtree_size(&node.left),
+                Ordering::Greater => {
+                    rank += Node::subtree_size(&node.left) + 1;
+                    subtree = node.right.as_deref();
+                }
+            }
+        }
+        rank
+    }
+
+    /**
+     * Position of the existing key in ascending order, starting from zero,
+     * i.e. the inverse of `nth`. Returns None, when the key is missing.
+     */
+    pub fn position_of(&self, key: &K) -> Option<usize> {
+        self.contains(key).then(|| self.rank(key))
+    }
+
+    /**
+     * Check the search tree invariant: every key is greater than all keys
+     * in its left subtree and less than all keys in its right subtree.
+     * Each node is checked against the bounds inherited from its ancestors.
+     */
+    pub fn is_valid_bst(&self) -> bool {
+        let mut stack = Vec::new();
+        if let Some(root) = self.root.as_deref() {
+            stack.push((root, None, None));
+        }
+
+        while let Some((node, low, high)) = stack.pop() {
+            let above_low = low.is_none_or(|low: &K| *low < node.key);
+            let below_high = high.is_none_or(|high: &K| node.key < *high);
+            if !above_low || !below_high {
+                return false;
+            }
+
+            if let Some(left_node) = node.left.as_deref() {
+                stack.push((left_node, low, Some(&node.key)));
+            }
+            if let Some(right_node) = node.right.as_deref() {
+                stack.push((right_node, Some(&node.key), high));
+            }
+        }
+        true
+    }
+
+    /**
+     * Height of the tree, like `height`, or None, when the tree is corrupt:
+     * a key is out of the bounds of its ancestors, like in `is_valid_bst`,
+     * or a node does not point to its actual parent.
+     */
+    pub fn checked_height(&self) -> Option<usize> {
+        let mut height = 0;
+        let mut stack = Vec::new();
+        if let Some(root) = self.root.as_deref() {
+            stack.push((root, None, None, None, 1));
+        }
+
+        while let Some((node, parent, low, high, depth)) = stack.pop() {
+            let above_low = low.is_none_or(|low: &K| *low < node.key);
+            let below_high = high.is_none_or(|high: &K| node.key < *high);
+            let parent_matches = match (node.parent, parent) {
+                (None, None) => true,
+                (Some(actual), Some(expected)) => ptr::eq(actual, expected),
+                _ => false,
+            };
+            if !above_low || !below_high || !parent_matches {
+                return None;
+            }
+            height = height.max(depth);
+
+            let node_ptr: *const Node<K, V> = node;
+            if let Some(left_node) = node.left.as_deref() {
+                stack.push((left_node, Some(node_ptr), low, Some(&node.key), depth + 1));
+            }
+            if let Some(right_node) = node.right.as_deref() {
+                stack.push((right_node, Some(node_ptr), Some(&node.key), high, depth + 1));
+            }
+        }
+        Some(height)
+    }
+
+    /**
+     * Number of keys in closed interval, zero when `low > high`.
+     * Uses ranks of both bounds, so no entries are visited.
+     */
+    pub fn count_range(&self, low: &K, high: &K) -> usize {
+        self.rank_range(low, high).len()
+    }
+
+    /**
+     * Positions of keys in closed interval `[low, high]` in sorted order,
+     * as half-open range `start..end`: `start` is the rank of `low`,
+     * `end` is the position after the last key, which is not greater
+     * than `high`. The range is empty, when `low > high`.
+     */
+    pub fn rank_range(&self, low: &K, high: &K) -> core::ops::Range<usize> {
+        let start = self.rank(low);
+        if low > high {
+            return start..start;
+        }
+        let end = self.rank(high) + usize::from(self.contains(high));
+        start..end
+    }
+
+    /**
+     * Depth of the node with the key: 0 for the root, 1 for its children
+     * and so on. Returns None, when the key is not present in the tree.
+     */
+    pub fn depth_of(&self, key: &K) -> Option<usize> {
+        let mut depth = 0;
+        let mut subtree = self.root.as_deref();
+        while let Some(node) = subtree {
+            subtree = match key.cmp(&node.key) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Equal => return Some(depth),
+                Ordering::Greater => node.right.as_deref(),
+            };
+            depth += 1;
+        }
+        None
+    }
+
+    /**
+     * Key of the parent of the node with the key: `Some(None)` for the
+     * root, and None, when the key is not present in the tree.
+     */
+    pub fn parent_key(&self, key: &K) -> Option<Option<&K>> {
+        let node = self.find_node(key)?;
+        Some(node.parent_node().map(|parent| &parent.key))
+    }
+
+    /**
+     * Keys of the left and the right child of the node with the key,
+     * or None, when the key is not present in the tree.
+     */
+    pub fn children_keys(&self, key: &K) -> Option<(Option<&K>, Option<&K>)> {
+        let node = self.find_node(key)?;
+        let left = node.left.as_deref().map(|left_node| &left_node.key);
+        let right = node.right.as_deref().map(|right_node| &right_node.key);
+        Some((left, right))
+    }
+
+    /**
+     * Keys visited on the way from the root down to the key, including
+     * the key itself. Returns None, when the key is not present in the tree.
+     */
+    pub fn path_to(&self, key: &K) -> Option<Vec<&K>> {
+        let mut path = Vec::new();
+        let mut subtree = self.root.as_deref();
+        while let Some(node) = subtree {
+            path.push(&node.key);
+            subtree = match key.cmp(&node.key) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Equal => return Some(path),
+                Ordering::Greater => node.right.as_deref(),
+            };
+        }
+        None
+    }
+
+    /**
+     * Deepest key, which is an ancestor of both keys, where a node is
+     * an ancestor of itself. Returns None, when any key is not present.
+     */
+    pub fn lowest_common_ancestor(&self, a: &K, b: &K) -> Option<&K> {
+        if !self.contains(a) || !self.contains(b) {
+            return None;
+        }
+
+        let mut subtree = self.root.as_deref();
+        while let Some(node) = subtree {
+            subtree = match (a.cmp(&node.key), b.cmp(&node.key)) {
+                (Ordering::Less, Ordering::Less) => node.left.as_deref(),
+                (Ordering::Greater, Ordering::Greater) => node.right.as_deref(),
+                _ => return Some(&node.key),
+            };
+        }
+        None
+    }
+
+    fn find_node<Q>(&self, key: &Q) -> Option<&Node<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut subtree = self.root.as_deref();
+        while let Some(node) = subtree {
+            subtree = match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+            };
+        }
+        None
+    }
+
+    fn find_node_mut<Q>(&mut self, key: &Q) -> Option<&mut Node<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Self::find_in_subtree_mut(self.root.as_deref_mut(), key)
+    }
+
+    fn find_in_subtree_mut<'a, Q>(
+        mut subtree: Option<&'a mut Node<K, V>>,
+        key: &Q,
+    ) -> Option<&'a mut Node<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        while let Some(node) = subtree {
+            subtree = match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return Some(node),
+                Ordering::Less => node.left.as_deref_mut(),
+                Ordering::Greater => node.right.as_deref_mut(),
+            };
+        }
+        None
+    }
+
+    /**
+     * Remove node with subtrees from tree and return them as a tree,
+     * like `detach_subtree`. The detached node becomes the root of
+     * the returned tree, which frees its nodes without recursion.
+     */
+    pub fn detach(&mut self, key: K) -> Option<Tree<K, V>> {
+        self.detach_subtree(&key)
+    }
+
+    /**
+     * Remove node with the key and its subtrees from the tree and return
+     * them as an independent tree.
+     */
+    pub fn detach_subtree(&mut self, key: &K) -> Option<Tree<K, V>> {
+        let mut root = self.detach_at(key)?;
+        root.parent = None;
+        self.len -= root.size;
+        let len = root.size;
+        Some(Tree::from_root(Some(root), len))
+    }
+
+    /**
+     * Attach all entries of the subtree at once, as a child of an existing
+     * node. It is possible, when the empty place of the subtree root lies
+     * between keys of the tree, which bound all keys of the subtree,
+     * otherwise the subtree is returned back.
+     */
+    pub fn attach_subtree(&mut self, mut subtree: Tree<K, V>) -> Result<(), Tree<K, V>> {
+        let (Some(low), Some(high)) = (subtree.min(), subtree.max()) else {
+            return Ok(());
+        };
+        let (low, high) = (low.0, high.0);
+
+        let mut parent = None;
+        let mut slot = &mut self.root;
+        while let Some(node) = slot {
+            let node_ptr: *mut Node<K, V> = &mut **node;
+            slot = match (high.cmp(&node.key), low.cmp(&node.key)) {
+                (Ordering::Less, _) => &mut node.left,
+                (_, Ordering::Greater) => &mut node.right,
+                _ => return Err(subtree),
+            };
+            parent = Some(node_ptr);
+        }
+
+        self.len += subtree.len;
+        Self::splice(slot, subtree.root.take(), parent);
+        Node::fix_sizes_upwards(parent);
+        Ok(())
+    }
+
+    fn detach_at(&mut self, key: &K) -> Option<Box<Node<K, V>>> {
+        let detached = self.slot_of(key)?.take()?;
+        Node::fix_sizes_upwards(detached.parent);
+        Some(detached)
+    }
+
+    /**
+     * Remove node with the key from tree and return its value.
+     * Children of the removed node are reattached to the tree,
+     * so all other keys stay reachable.
+     */
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.take(key).map(|(_, value)| value)
+    }
+
+    /**
+     * Remove node with the key from tree like `remove`, and return
+     * the stored key together with the value.
+     */
+    pub fn take<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let removed = Self::unlink(self.slot_of(key)?);
+
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /**
+     * Unlink node stored in the slot and return its key and value.
+     * A node with two children is replaced by its in-order successor,
+     * so the node itself stays in place and parent pointers of its
+     * children remain valid. Sizes of ancestors are fixed up to the root.
+     */
+    fn unlink(slot: &mut Option<Box<Node<K, V>>>) -> Option<(K, V)> {
+        let current_node = slot.as_deref_mut()?;
+
+        if current_node.left.is_some() && current_node.right.is_some() {
+            let successor = Self::detach_min(&mut current_node.right)?;
+            let removed = (
+                core::mem::replace(&mut current_node.key, successor.key),
+                core::mem::replace(&mut current_node.value, successor.value),
+            );
+            Node::fix_sizes_upwards(successor.parent);
+            return Some(removed);
+        }
+
+        let mut removed_node = slot.take()?;
+        let child = removed_node.left.take().or(removed_node.right.take());
+        Self::splice(slot, child, removed_node.parent);
+        Node::fix_sizes_upwards(removed_node.parent);
+        Some((removed_node.key, removed_node.value))
+    }
+
+    /**
+     * Detach the leftmost node of the subtree stored in the slot.
+     * Right child of the detached node takes its place. Sizes are left
+     * for the caller to fix, starting from the parent of the detached node.
+     */
+    fn detach_min(mut slot: &mut Option<Box<Node<K, V>>>) -> Option<Box<Node<K, V>>> {
+        while slot.as_ref()?.left.is_some() {
+            slot = &mut slot.as_mut()?.left;
+        }
+
+        let mut min_node = slot.take()?;
+        let right = min_node.right.take();
+        Self::splice(slot, right, min_node.parent);
+        Some(min_node)
+    }
+
+    /**
+     * Remove the entry with the smallest key and return it.
+     */
+    pub fn pop_min(&mut self) -> Option<(K, V)> {
+        self.remove_min()
+    }
+
+    /**
+     * Remove the entry with the largest key and return it.
+     */
+    pub fn pop_max(&mut self) -> Option<(K, V)> {
+        self.remove_max()
+    }
+
+    /**
+     * Remove the entry with the smallest key and return it. Takes O(h)
+     * time without recursion and allocations, so a tree can be drained
+     * in ascending order like a priority queue.
+     */
+    pub fn remove_min(&mut self) -> Option<(K, V)> {
+        self.remove_extreme(Ordering::Less)
+    }
+
+    /**
+     * Remove the entry with the largest key and return it, like
+     * `remove_min`.
+     */
+    pub fn remove_max(&mut self) -> Option<(K, V)> {
+        self.remove_extreme(Ordering::Greater)
+    }
+
+    /**
+     * Walk down to the leftmost node for `Ordering::Less` or the rightmost
+     * one otherwise, unlink it and put its only child into its place.
+     */
+    fn remove_extreme(&mut self, side: Ordering) -> Option<(K, V)> {
+        let mut slot = &mut self.root;
+        loop {
+            let node = slot.as_deref()?;
+            let next = match side {
+                Ordering::Less => &node.left,
+                _ => &node.right,
+            };
+            if next.is_none() {
+                break;
+            }
+            let node = slot.as_deref_mut()?;
+            slot = match side {
+                Ordering::Less => &mut node.left,
+                _ => &mut node.right,
+            };
+        }
+
+        let mut extreme_node = slot.take()?;
+        let child = match side {
+            Ordering::Less => extreme_node.right.take(),
+            _ => extreme_node.left.take(),
+        };
+        Self::splice(slot, child, extreme_node.parent);
+        Node::fix_sizes_upwards(extreme_node.parent);
+        self.len -= 1;
+        Some((extreme_node.key, extreme_node.value))
+    }
+
+    /**
+     * Rotate the subtree rooted at the key to the left, so its right child
+     * takes its place. Returns false, when the key is missing or the node
+     * has no right child.
+     */
+    pub fn rotate_left(&mut self, key: &K) -> bool {
+        self.slot_of(key).is_some_and(Self::rotate_left_at)
+    }
+
+    /**
+     * Rotate the subtree rooted at the key to the right, so its left child
+     * takes its place. Returns false, when the key is missing or the node
+     * has no left child.
+     */
+    pub fn rotate_right(&mut self, key: &K) -> bool {
+        self.slot_of(key).is_some_and(Self::rotate_right_at)
+    }
+
+    /**
+     * Find the slot, which owns the node with the key.
+     */
+    fn slot_of<Q>(&mut self, key: &Q) -> Option<&mut Option<Box<Node<K, V>>>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut slot = &mut self.root;
+        loop {
+            match key.cmp(slot.as_ref()?.key.borrow()) {
+                Ordering::Equal => return Some(slot),
+                Ordering::Less => slot = &mut slot.as_mut()?.left,
+                Ordering::Greater => slot = &mut slot.as_mut()?.right,
+            }
+        }
+    }
+}
+
+impl<K, V> Default for Tree<K, V> {
+    /**
+     * Empty tree. Unlike `new`, no bounds on the key are required.
+     */
+    fn default() -> Self {
+        Self::from_root(None, 0)
+    }
+}
+
+impl<K, V> Drop for Tree<K, V> {
+    /**
+     * Free nodes in a loop, like `clear`, because the default drop
+     * of nested boxes recurses once per level of the tree.
+     */
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<K, V> Clone for Tree<K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    /**
+     * Deep copy of the tree of the same shape.
+     */
+    fn clone(&self) -> Self {
+        self.map_values(V::clone)
+    }
+
+    /**
+     * Copy the source into this tree, reusing nodes of this tree for
+     * copies of source nodes, so only missing nodes are allocated
+     * and only extra nodes are freed.
+     */
+    fn clone_from(&mut self, source: &Self) {
+        let mut spare = Vec::with_capacity(self.len);
+        let mut stack: Vec<Box<Node<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+            spare.push(node);
+        }
+        self.len = source.len;
+        self.duplicates_rejected = 0;
+
+        let mut copy = |source: &Node<K, V>, parent: Option<*mut Node<K, V>>| {
+            let mut node = match spare.pop() {
+                Some(mut node) => {
+                    node.key.clone_from(&source.key);
+                    node.value.clone_from(&source.value);
+                    node
+                }
+                None => Box::new(Node::new_root(source.key.clone(), source.value.clone())),
+            };
+            node.parent = parent;
+            node.size = source.size;
+            node
+        };
+
+        let Some(source_root) = source.root.as_deref() else {
+            return;
+        };
+        let target_root = self.root.insert(copy(source_root, None));
+        let mut stack = vec![(source_root, &mut **target_root as *mut Node<K, V>)];
+        while let Some((source, target_ptr)) = stack.pop() {
+            // Each copied node is pushed to the stack once, so no other
+            // reference to it exists while it's modified.
+            let target = unsafe { &mut *target_ptr };
+
+            if let Some(source_left) = source.left.as_deref() {
+                let target_left = target.left.insert(copy(source_left, Some(target_ptr)));
+                stack.push((source_left, &mut **target_left));
+            }
+            if let Some(source_right) = source.right.as_deref() {
+                let target_right = target.right.insert(copy(source_right, Some(target_ptr)));
+                stack.push((source_right, &mut **target_right));
+            }
+        }
+    }
+}
+
+impl<K, V> PartialEq for Tree<K, V>
+where
+    K: PartialEq,
+    V: PartialEq,
+{
+    /**
+     * Trees are equal, when they contain equal entries in the same order,
+     * regardless of their shape.
+     */
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<K, V> Eq for Tree<K, V>
+where
+    K: Eq,
+    V: Eq,
+{
+}
+
+impl<K, V> PartialOrd for Tree<K, V>
+where
+    K: PartialOrd,
+    V: PartialOrd,
+{
+    /**
+     * Compare entries of trees lexicographically in key order. Wrap trees
+     * into `OrdTree` for total order.
+     */
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<K, V> Hash for Tree<K, V>
+where
+    K: Hash,
+    V: Hash,
+{
+    /**
+     * Hash the length and entries in key order, so equal trees of
+     * different shape have equal hashes.
+     */
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len);
+        for entry in self.iter() {
+            entry.hash(state);
+        }
+    }
+}
+
+impl<K, V> Index<&K> for Tree<K, V>
+where
+    K: Ord,
+{
+    type Output = V;
+
+    /**
+     * Get reference to the value stored under the key.
+     *
+     * ```
+     * use basic_binary_tree::Tree;
+     *
+     * let tree: Tree<i32, i32> = [5, 3, 65, 123, 6, 11, 3, 1, 5, 42]
+     *     .into_iter()
+     *     .map(|i| (i, i))
+     *     .collect();
+     * assert_eq!(tree[&6], 6);
+     * ```
+     *
+     * Panics, when the key is not present in the tree.
+     */
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("key is not found in the tree")
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for Tree<K, V>
+where
+    K: Ord,
+{
+    /**
+     * Build a tree by inserting pairs in iterator order.
+     * Like `insert`, later pairs with duplicate keys replace earlier values.
+     */
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut tree = Self::new();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<K, V> From<BTreeMap<K, V>> for Tree<K, V>
+where
+    K: Ord,
+{
+    /**
+     * Build a balanced tree from the map, which is already sorted by key.
+     */
+    fn from(map: BTreeMap<K, V>) -> Self {
+        let len = map.len();
+        Self::from_root(Self::build_balanced(&mut map.into_iter(), len), len)
+    }
+}
+
+impl<K, V> Extend<(K, V)> for Tree<K, V>
+where
+    K: Ord,
+{
+    /**
+     * Insert pairs in iterator order. Like `insert`, pairs with keys,
+     * which are already present in the tree, replace existing values.
+     */
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn demo_tree() -> Tree<i32, i32> {
+        let mut tree = Tree::new();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i);
+        }
+        tree
+    }
+
+    /**
+     * Build a right-leaning chain of nodes directly, without recursion.
+     */
+    fn degenerate_chain(len: i32) -> Tree<i32, i32> {
+        let mut tree = Tree::new();
+        let mut slot = &mut tree.root;
+        let mut parent = None;
+        for key in 0..len {
+            let node = slot.insert(Box::new(Node {
+                key,
+                value: key,
+                left: None,
+                right: None,
+                parent,
+                size: (len - key) as usize,
+            }));
+            parent = Some(&mut **node as *mut Node<i32, i32>);
+            slot = &mut node.right;
+        }
+        tree.len = len as usize;
+        tree
+    }
+
+    #[test]
+    fn remove_root_with_two_children() {
+        let mut tree = demo_tree();
+
+        assert_eq!(tree.remove(&5), Some(5));
+        assert!(tree.find(5).is_none());
+        for key in [1, 3, 6, 11, 42, 65, 123] {
+            assert!(tree.find(key).is_some(), "key {} is lost", key);
+        }
+        assert_eq!(tree.root.as_ref().map(|node| node.key), Some(6));
+        assert!(tree.is_valid_bst());
+        assert_parent_pointers(&tree);
+    }
+
+    #[test]
+    fn remove_interior_node_with_two_children() {
+        let mut tree = demo_tree();
+
+        assert_eq!(tree.remove(&65), Some(65));
+        assert!(tree.find(65).is_none());
+        for key in [1, 3, 5, 6, 11, 42, 123] {
+            assert!(tree.find(key).is_some(), "key {} is lost", key);
+        }
+        assert!(tree.is_valid_bst());
+        assert_parent_pointers(&tree);
+    }
+
+    #[test]
+    fn remove_leaf_and_single_child_nodes() {
+        let mut tree = demo_tree();
+
+        assert_eq!(tree.remove(&42), Some(42));
+        assert_eq!(tree.remove(&6), Some(6));
+        assert_eq!(tree.remove(&3), Some(3));
+        assert_eq!(tree.remove(&3), None);
+        for key in [1, 5, 11, 65, 123] {
+            assert!(tree.find(key).is_some(), "key {} is lost", key);
+        }
+        assert!(tree.is_valid_bst());
+        assert_parent_pointers(&tree);
+    }
+
+    #[test]
+    fn contains_borrows_the_key() {
+        let mut tree = Tree::new();
+        for name in ["beta", "alpha", "gamma"] {
+            tree.insert(name.to_string(), name.len());
+        }
+
+        let alpha = "alpha".to_string();
+        assert!(tree.contains(&alpha));
+        assert!(tree.contains(&"gamma".to_string()));
+        assert!(!tree.contains(&"delta".to_string()));
+        assert_eq!(alpha, "alpha");
+    }
+
+    #[test]
+    fn find_mut_changes_only_value() {
+        let mut tree = demo_tree();
+
+        let mut node = tree.find_mut(&65).unwrap();
+        assert_eq!(node.key(), &65);
+        assert_eq!((node.left_key(), node.right_key()), (Some(&6), Some(&123)));
+        *node.value_mut() += 1;
+        assert_eq!(node.value(), &66);
+        assert_eq!(tree.get(&65), Some(&66));
+
+        *tree.find_mut(&1).unwrap().into_value_mut() = 10;
+        assert_eq!(tree.get(&1), Some(&10));
+        assert!(tree.find_mut(&7).is_none());
+    }
+
+    #[test]
+    fn get_and_get_mut_return_values() {
+        let mut tree = demo_tree();
+
+        assert_eq!(tree.get(&42), Some(&42));
+        assert_eq!(tree.get(&7), None);
+
+        *tree.get_mut(&42).unwrap() = 4200;
+        assert_eq!(tree.get(&42), Some(&4200));
+        assert_eq!(tree.get_mut(&7), None);
+    }
+
+    #[test]
+    fn len_counts_distinct_keys() {
+        let mut tree = Tree::new();
+        assert!(tree.is_empty());
+        assert_eq!(tree.len(), 0);
+
+        let mut tree_with_duplicates = demo_tree();
+        assert_eq!(tree_with_duplicates.len(), 8);
+        assert_eq!(tree_with_duplicates.insert(5, 0), Some(5));
+        assert_eq!(tree_with_duplicates.len(), 8);
+
+        tree.insert(1, 1);
+        assert!(!tree.is_empty());
+        assert_eq!(tree.len(), 1);
+    }
+
+    #[test]
+    fn len_follows_remove_and_detach() {
+        let mut tree = demo_tree();
+
+        tree.remove(&5);
+        tree.remove(&5);
+        assert_eq!(tree.len(), 7);
+
+        let detached = tree.detach(65);
+        assert!(detached.is_some());
+        assert_eq!(tree.len(), 3);
+    }
+
+    #[test]
+    fn clear_empties_the_tree() {
+        let mut tree = demo_tree();
+
+        tree.clear();
+        assert!(tree.is_empty());
+        assert_eq!(tree.get(&5), None);
+
+        tree.insert(7, 7);
+        assert_eq!(tree.len(), 1);
+    }
+
+    #[test]
+    fn clear_deep_chain_without_stack_overflow() {
+        let mut tree = degenerate_chain(100_000);
+        assert_eq!(tree.len(), 100_000);
+
+        tree.clear();
+        assert!(tree.is_empty());
+        assert!(tree.root.is_none());
+    }
+
+    #[test]
+    fn lookup_in_deep_chain_without_stack_overflow() {
+        let mut tree = degenerate_chain(100_000);
+
+        assert_eq!(tree.get(&99_999), Some(&99_999));
+        assert_eq!(tree.find(99_998).map(Node::key), Some(&99_998));
+        assert!(!tree.contains(&100_000));
+        *tree.get_mut(&99_999).unwrap() = 0;
+        assert_eq!(tree.get(&99_999), Some(&0));
+    }
+
+    #[test]
+    fn structure_of_deep_chain_without_stack_overflow() {
+        let tree = degenerate_chain(100_000);
+
+        let debug = tree.to_structure_string();
+        assert!(debug.starts_with("(0: 0 _ (1: 1 _ (2: 2 _"));
+        let end = format!("(99999: 99999){}", ")".repeat(99_999));
+        assert!(debug.ends_with(&end));
+    }
+
+    #[test]
+    fn into_iter_deep_chain_without_stack_overflow() {
+        let mut iter = degenerate_chain(500_000).into_iter();
+        assert!(iter.by_ref().take(250_000).map(|(k, _)| k).eq(0..250_000));
+
+        // The rest of the chain is freed, when the iterator is dropped.
+        drop(iter);
+
+        let tree = degenerate_chain(500_000);
+        assert_eq!(tree.into_iter().count(), 500_000);
+    }
+
+    #[test]
+    fn drop_deep_chain_without_stack_overflow() {
+        let thread = std::thread::Builder::new().stack_size(64 * 1024);
+        let handle = thread
+            .spawn(|| {
+                let tree = degenerate_chain(500_000);
+                assert_eq!(tree.len(), 500_000);
+            })
+            .unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn drop_detached_deep_chain_without_stack_overflow() {
+        let thread = std::thread::Builder::new().stack_size(64 * 1024);
+        let handle = thread
+            .spawn(|| {
+                let mut tree = degenerate_chain(20_000);
+                let detached = tree.detach(1).unwrap();
+                assert_eq!(detached.len(), 19_999);
+                assert_eq!(tree.len(), 1);
+                drop(detached);
+
+                let detached = tree.detach(0).unwrap();
+                assert!(tree.is_empty());
+                assert_eq!(detached.len(), 1);
+            })
+            .unwrap();
+        handle.join().unwrap();
+    }
+
+    /**
+     * Ascending run builds a chain, so each insert walks the whole tree.
+     * Insertion into a chain is quadratic, so the run is kept short, but
+     * the thread stack is small enough to overflow with recursive insert.
+     */
+    #[test]
+    fn insert_ascending_run_without_stack_overflow() {
+        let thread = std::thread::Builder::new().stack_size(64 * 1024);
+        let handle = thread
+            .spawn(|| {
+                let mut tree = Tree::new();
+                for i in 0..10_000 {
+                    assert_eq!(tree.insert(i, i), None);
+                }
+                assert_eq!(tree.insert(0, 0), Some(0));
+                assert_eq!(tree.len(), 10_000);
+                assert_eq!(tree.height(), 10_000);
+                assert_eq!(tree.max(), Some((&9_999, &9_999)));
+            })
+            .unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn remove_in_deep_chain_without_stack_overflow() {
+        let thread = std::thread::Builder::new().stack_size(64 * 1024);
+        let handle = thread
+            .spawn(|| {
+                let mut tree = degenerate_chain(200_000);
+                assert_eq!(tree.remove(&199_999), Some(199_999));
+                assert_eq!(tree.remove(&100_000), Some(100_000));
+                assert_eq!(tree.remove(&200_000), None);
+                let subtree = tree.detach_subtree(&150_000).unwrap();
+                assert_eq!(subtree.len(), 49_999);
+                assert_eq!(tree.len(), 149_999);
+                assert_sizes(&tree);
+
+                // The successor of the root is the deepest node of a chain.
+                let mut tree = Tree::new();
+                tree.insert(0, 0);
+                tree.insert(-1, -1);
+                for i in (1..=10_000).rev() {
+                    tree.insert(i, i);
+                }
+                assert_eq!(tree.remove(&0), Some(0));
+                assert_eq!(tree.root.as_ref().map(|root| root.key), Some(1));
+                assert_eq!(tree.len(), 10_001);
+                assert_sizes(&tree);
+            })
+            .unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn min_and_max_entries() {
+        let tree = demo_tree();
+        assert_eq!(tree.min(), Some((&1, &1)));
+        assert_eq!(tree.max(), Some((&123, &123)));
+
+        let empty: Tree<i32, i32> = Tree::new();
+        assert_eq!(empty.min(), None);
+        assert_eq!(empty.max(), None);
+    }
+
+    #[test]
+    fn first_and_last_entries_are_mutable() {
+        let mut tree = demo_tree();
+
+        let (key, value) = tree.first_entry().unwrap();
+        assert_eq!(*key, 1);
+        *value = 100;
+        *tree.last_entry().unwrap().1 += 1;
+        assert_eq!(tree.first_key_value(), Some((&1, &100)));
+        assert_eq!(tree.last_key_value(), Some((&123, &124)));
+
+        let mut empty: Tree<i32, i32> = Tree::new();
+        assert_eq!(empty.first_entry(), None);
+        assert_eq!(empty.last_entry(), None);
+    }
+
+    #[test]
+    fn successor_and_predecessor_follow_key_order() {
+        let tree = demo_tree();
+        let keys = [1, 3, 5, 6, 11, 42, 65, 123];
+
+        for pair in keys.windows(2) {
+            assert_eq!(tree.successor(&pair[0]), Some(&pair[1]));
+            assert_eq!(tree.predecessor(&pair[1]), Some(&pair[0]));
+        }
+        assert_eq!(tree.successor(&123), None);
+        assert_eq!(tree.predecessor(&1), None);
+        assert_eq!(tree.successor(&7), None);
+    }
+
+    #[test]
+    fn successor_and_predecessor_entries() {
+        let mut tree = demo_tree();
+        assert_eq!(tree.successor_entry(&6), Some((&11, &11)));
+        *tree.get_mut(&11).unwrap() = 110;
+
+        assert_eq!(tree.successor_entry(&6), Some((&11, &110)));
+        assert_eq!(tree.predecessor_entry(&42), Some((&11, &110)));
+        assert_eq!(tree.successor_entry(&123), None);
+        assert_eq!(tree.predecessor_entry(&1), None);
+        assert_eq!(tree.successor_entry(&7), None);
+    }
+
+    #[test]
+    fn successor_after_remove() {
+        let mut tree = demo_tree();
+
+        tree.remove(&5);
+        tree.remove(&11);
+        assert_eq!(tree.successor(&3), Some(&6));
+        assert_eq!(tree.successor(&6), Some(&42));
+        assert_eq!(tree.predecessor(&42), Some(&6));
+    }
+
+    #[test]
+    fn between_existing_keys() {
+        let tree = demo_tree();
+
+        assert!(tree.between(&3, &42).map(|(k, _)| *k).eq([5, 6, 11]));
+        assert!(tree
+            .between(&1, &123)
+            .map(|(k, _)| *k)
+            .eq([3, 5, 6, 11, 42, 65]));
+        assert_eq!(tree.between(&5, &6).count(), 0);
+        assert_eq!(tree.between(&42, &3).count(), 0);
+        assert_eq!(tree.between(&6, &6).count(), 0);
+        assert_eq!(tree.between(&2, &42).count(), 0);
+        assert_eq!(tree.between(&3, &100).count(), 0);
+    }
+
+    #[test]
+    fn floor_and_ceiling_of_missing_and_present_keys() {
+        let tree = demo_tree();
+
+        assert_eq!(tree.floor(&6), Some(&6));
+        assert_eq!(tree.ceiling(&6), Some(&6));
+        assert_eq!(tree.floor(&10), Some(&6));
+        assert_eq!(tree.ceiling(&10), Some(&11));
+        assert_eq!(tree.floor(&100), Some(&65));
+        assert_eq!(tree.ceiling(&100), Some(&123));
+
+        assert_eq!(tree.floor(&0), None);
+        assert_eq!(tree.ceiling(&0), Some(&1));
+        assert_eq!(tree.floor(&1000), Some(&123));
+        assert_eq!(tree.ceiling(&1000), None);
+    }
+
+    #[test]
+    fn get_disjoint_mut_of_distinct_keys() {
+        let mut tree = demo_tree();
+
+        let [three, six, forty_two] = tree.get_disjoint_mut([&3, &6, &42]).unwrap();
+        *three += 1;
+        *six *= 10;
+        core::mem::swap(six, forty_two);
+        assert_eq!(
+            tree.get_many([&3, &6, &42]),
+            [Some(&4), Some(&42), Some(&60)]
+        );
+
+        assert!(tree.get_disjoint_mut([&3, &6, &3]).is_none());
+        assert!(tree.get_disjoint_mut([&3, &7]).is_none());
+        assert_eq!(tree.get_disjoint_mut::<0>([]), Some([]));
+        assert_eq!(tree.get(&3), Some(&4));
+    }
+
+    #[test]
+    fn get_disjoint_mut_along_one_path() {
+        let mut tree = demo_tree();
+        assert_eq!(tree.root.as_ref().map(|root| root.key), Some(5));
+
+        // The root, its child and grandchild, in both orders.
+        let [root, child, grandchild] = tree.get_disjoint_mut([&5, &3, &1]).unwrap();
+        *root += 100;
+        *child += 100;
+        *grandchild += 100;
+        let [grandchild, child, root] = tree.get_disjoint_mut([&1, &3, &5]).unwrap();
+        core::mem::swap(root, grandchild);
+        *child += 1;
+
+        assert_eq!(
+            tree.get_many([&5, &3, &1]),
+            [Some(&101), Some(&104), Some(&105)]
+        );
+        assert!(tree.get_disjoint_mut([&5, &5]).is_none());
+        assert!(Tree::<i32, i32>::new().get_disjoint_mut([&5]).is_none());
+    }
+
+    #[test]
+    fn swap_values_of_two_keys() {
+        let mut tree = demo_tree();
+        *tree.get_mut(&65).unwrap() = 650;
+
+        assert!(tree.swap_values(&3, &65));
+        assert_eq!(tree.get(&3), Some(&650));
+        assert_eq!(tree.get(&65), Some(&3));
+
+        // The root and a key in its subtree, and keys in one subtree.
+        assert!(tree.swap_values(&5, &1));
+        assert!(tree.swap_values(&42, &6));
+        assert!(tree.swap_values(&11, &11));
+        assert_eq!(
+            tree.get_many([&1, &5, &6, &42]),
+            [Some(&5), Some(&1), Some(&42), Some(&6)]
+        );
+
+        assert!(!tree.swap_values(&3, &4));
+        assert!(!tree.swap_values(&4, &4));
+        assert!(!tree.swap_values(&1000, &5));
+        assert_eq!(tree.get(&3), Some(&650));
+        assert_eq!(tree.get(&5), Some(&1));
+    }
+
+    #[test]
+    fn lower_and_upper_bounds() {
+        let tree = demo_tree();
+
+        assert_eq!(tree.lower_bound(&6), Some((&6, &6)));
+        assert_eq!(tree.upper_bound(&6), Some((&11, &11)));
+        assert_eq!(tree.lower_bound(&7), Some((&11, &11)));
+        assert_eq!(tree.upper_bound(&7), Some((&11, &11)));
+        assert_eq!(tree.lower_bound(&0), Some((&1, &1)));
+        assert_eq!(tree.upper_bound(&65), Some((&123, &123)));
+        assert_eq!(tree.lower_bound(&123), Some((&123, &123)));
+        assert_eq!(tree.upper_bound(&123), None);
+    }
+
+    #[test]
+    fn height_of_demo_tree() {
+        let tree = demo_tree();
+        assert_eq!(tree.height(), 5);
+        assert!(!tree.is_balanced());
+
+        let empty: Tree<i32, i32> = Tree::new();
+        assert_eq!(empty.height(), 0);
+        assert!(empty.is_balanced());
+    }
+
+    #[test]
+    fn height_and_balance_of_small_trees() {
+        let mut tree = Tree::new();
+        tree.insert(2, 2);
+        assert_eq!(tree.height(), 1);
+
+        tree.insert(1, 1);
+        tree.insert(3, 3);
+        assert_eq!(tree.height(), 2);
+        assert!(tree.is_balanced());
+
+        tree.insert(4, 4);
+        assert!(tree.is_balanced());
+        tree.insert(5, 5);
+        assert_eq!(tree.height(), 4);
+        assert!(!tree.is_balanced());
+    }
+
+    #[test]
+    fn min_depth_stops_at_first_leaf() {
+        assert_eq!(demo_tree().min_depth(), 3);
+        assert_eq!(Tree::<i32, i32>::new().min_depth(), 0);
+
+        let balanced = Tree::from_sorted((0..7).map(|i| (i, i)).collect());
+        assert_eq!(balanced.min_depth(), 3);
+        assert_eq!(balanced.min_depth(), balanced.height());
+    }
+
+    #[test]
+    fn spines_follow_extreme_children() {
+        let tree = demo_tree();
+        assert_eq!(tree.left_spine(), [&5, &3, &1]);
+        assert_eq!(tree.right_spine(), [&5, &65, &123]);
+        assert!(Tree::<i32, i32>::new().left_spine().is_empty());
+
+        let chain = degenerate_chain(1_000);
+        assert_eq!(chain.left_spine(), [&0]);
+        assert_eq!(chain.right_spine().len(), chain.len());
+    }
+
+    #[test]
+    fn diameter_may_bypass_root() {
+        let balanced = Tree::from_sorted((0..7).map(|i| (i, i)).collect());
+        assert_eq!(balanced.diameter(), 5);
+        assert_eq!(Tree::<i32, i32>::new().diameter(), 0);
+
+        assert_eq!(demo_tree().diameter(), 7);
+
+        // The longest path 15, 20, 30, 50, 70, 80, 90 is below the root.
+        let keys = [10, 100, 50, 30, 70, 20, 80, 15, 90];
+        let tree: Tree<_, _> = keys.into_iter().map(|key| (key, key)).collect();
+        assert_eq!(tree.diameter(), 7);
+        assert_eq!(tree.height(), 6);
+
+        let chain = degenerate_chain(10_000);
+        assert_eq!(chain.diameter(), 10_000);
+    }
+
+    #[test]
+    fn height_of_deep_chain_without_stack_overflow() {
+        let tree = degenerate_chain(100_000);
+        assert_eq!(tree.height(), 100_000);
+        assert_eq!(tree.min_depth(), 100_000);
+        assert!(!tree.is_balanced());
+    }
+
+    #[test]
+    fn collect_into_tree() {
+        let tree: Tree<i32, i32> = [5, 3, 65, 123, 6, 11, 3, 1, 5, 42]
+            .into_iter()
+            .map(|i| (i, i))
+            .collect();
+
+        assert_eq!(tree.len(), 8);
+        assert!(tree.iter().eq(demo_tree().iter()));
+        assert_eq!(tree.height(), demo_tree().height());
+    }
+
+    #[test]
+    fn collect_keeps_last_value_of_duplicate_key() {
+        let tree: Tree<i32, &str> = vec![(1, "first"), (1, "second")].into_iter().collect();
+        assert_eq!(tree.get(&1), Some(&"second"));
+        assert_eq!(tree.len(), 1);
+    }
+
+    #[test]
+    fn remove_many_counts_removed_keys() {
+        let mut tree = demo_tree();
+
+        assert_eq!(tree.remove_many(&[3, 6, 999]), 2);
+        assert!(tree.keys().copied().eq([1, 5, 11, 42, 65, 123]));
+        assert_eq!(tree.len(), 6);
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+
+        assert_eq!(tree.remove_many([5, 5].iter()), 1);
+        assert_eq!(tree.remove_many(&[]), 0);
+    }
+
+    #[test]
+    fn insert_many_counts_new_keys() {
+        let mut tree = demo_tree();
+
+        assert_eq!(
+            tree.insert_many([(7, 700), (5, 500), (200, 2000), (1, 100)]),
+            2
+        );
+        assert_eq!(tree.len(), 10);
+        assert_eq!(tree.get(&5), Some(&500));
+        assert_eq!(tree.insert_many([(8, 8), (8, 80)]), 1);
+        assert_eq!(tree.get(&8), Some(&80));
+        assert_eq!(tree.insert_many(core::iter::empty()), 0);
+
+        let mut chain = Tree::new();
+        assert_eq!(chain.insert_many((0..10_000).map(|i| (i, i))), 10_000);
+        assert_sizes(&chain);
+    }
+
+    #[test]
+    fn extend_adds_new_keys_and_replaces_values() {
+        let mut tree = demo_tree();
+
+        tree.extend([(7, 700), (5, 500), (200, 2000), (1, 100)]);
+        assert_eq!(tree.len(), 10);
+        assert_eq!(tree.get(&7), Some(&700));
+        assert_eq!(tree.get(&200), Some(&2000));
+        assert_eq!(tree.get(&5), Some(&500));
+        assert_eq!(tree.get(&1), Some(&100));
+    }
+
+    #[test]
+    fn default_tree_is_empty() {
+        assert!(Tree::<i32, i32>::default().is_empty());
+
+        let mut tree = demo_tree();
+        let taken = std::mem::take(&mut tree);
+        assert!(tree.is_empty());
+        assert_eq!(taken.len(), 8);
+    }
+
+    #[test]
+    fn clone_is_independent_of_original() {
+        let tree = demo_tree();
+        let mut copy = tree.clone();
+
+        *copy.get_mut(&6).unwrap() = 600;
+        copy.remove(&5);
+        assert_eq!(copy.get(&6), Some(&600));
+        assert_eq!(tree.get(&6), Some(&6));
+        assert_eq!(tree.get(&5), Some(&5));
+        assert_eq!(tree.len(), 8);
+        assert_eq!(copy.len(), 7);
+    }
+
+    #[test]
+    fn clone_from_reuses_nodes() {
+        fn addresses(tree: &Tree<i32, i32>) -> Vec<usize> {
+            let mut addresses: Vec<usize> = tree
+                .preorder()
+                .map(|(key, _)| tree.find_node(key).unwrap() as *const _ as usize)
+                .collect();
+            addresses.sort_unstable();
+            addresses
+        }
+
+        let source = demo_tree();
+        let mut target = degenerate_chain(20);
+        let before = addresses(&target);
+
+        target.clone_from(&source);
+        assert_eq!(target, source);
+        assert!(target.structurally_equal(&source));
+        assert_eq!(target.len(), 8);
+        assert_parent_pointers(&target);
+        assert_sizes(&target);
+        assert_navigation(&target);
+        assert!(addresses(&target)
+            .iter()
+            .all(|a| before.binary_search(a).is_ok()));
+
+        let mut small = tree! { 1 => 1 };
+        small.clone_from(&degenerate_chain(50));
+        assert!(small.keys().copied().eq(0..50));
+        assert_parent_pointers(&small);
+        small.clone_from(&Tree::new());
+        assert!(small.is_empty());
+    }
+
+    #[test]
+    fn clone_rewires_parent_pointers() {
+        let tree = demo_tree();
+        let copy = tree.clone();
+        drop(tree);
+
+        let root = copy.root.as_deref().unwrap();
+        let left = root.left.as_deref().unwrap();
+        assert_eq!(left.parent, Some(root as *const Node<i32, i32> as *mut _));
+
+        assert_eq!(copy.successor(&3), Some(&5));
+        assert_eq!(copy.successor(&42), Some(&65));
+        assert_eq!(copy.predecessor(&6), Some(&5));
+        assert!(copy.iter().eq(demo_tree().iter()));
+    }
+
+    #[test]
+    fn trees_with_same_entries_are_equal() {
+        let first: Tree<i32, i32> = [3, 1, 2].into_iter().map(|i| (i, i)).collect();
+        let second: Tree<i32, i32> = [2, 3, 1].into_iter().map(|i| (i, i)).collect();
+        assert_ne!(first.height(), second.height());
+        assert_eq!(first, second);
+
+        let third: Tree<i32, i32> = [1, 2, 3].into_iter().map(|i| (i, i * 2)).collect();
+        assert_ne!(first, third);
+
+        let fourth: Tree<i32, i32> = [1, 2].into_iter().map(|i| (i, i)).collect();
+        assert_ne!(first, fourth);
+    }
+
+    #[test]
+    fn lookup_string_keys_by_str() {
+        let mut tree = Tree::new();
+        for name in ["beta", "alpha", "gamma"] {
+            tree.insert(name.to_string(), name.len());
+        }
+
+        assert_eq!(tree.get("alpha"), Some(&5));
+        assert!(tree.contains("gamma"));
+        assert!(!tree.contains("delta"));
+
+        *tree.get_mut("beta").unwrap() += 1;
+        assert_eq!(tree.remove("beta"), Some(5));
+        assert_eq!(tree.remove("beta"), None);
+        assert_eq!(tree.len(), 2);
+    }
+
+    #[test]
+    fn remove_range_cuts_window() {
+        let mut tree = demo_tree();
+        assert_eq!(tree.remove_range(&5, &42), 4);
+        assert!(tree.keys().copied().eq([1, 3, 65, 123]));
+        assert_eq!(tree.len(), 4);
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+        assert_navigation(&tree);
+
+        let mut tree = demo_tree();
+        assert_eq!(tree.remove_range(&7, &10), 0);
+        assert_eq!(tree.remove_range(&42, &3), 0);
+        assert_eq!(tree.remove_range(&0, &4), 2);
+        assert_eq!(tree.remove_range(&60, &1000), 2);
+        assert!(tree.keys().copied().eq([5, 6, 11, 42]));
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+
+        let mut tree = degenerate_chain(10_000);
+        assert_eq!(tree.remove_range(&100, &9_899), 9_800);
+        assert!(tree.keys().copied().eq((0..100).chain(9_900..10_000)));
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+
+        for (low, high) in [(0, 63), (10, 20), (30, 80), (-5, 5)] {
+            for seed in 0..20 {
+                let mut random = Random(seed);
+                let mut tree = Tree::new();
+                let mut reference = BTreeMap::new();
+                for _ in 0..50 {
+                    let key = random.below(64);
+                    tree.insert(key, key);
+                    reference.insert(key, key);
+                }
+                let before = reference.len();
+                reference.retain(|key, _| !(low..=high).contains(key));
+
+                assert_eq!(tree.remove_range(&low, &high), before - reference.len());
+                assert_matches_reference(&tree, &reference, "remove_range");
+            }
+        }
+    }
+
+    #[test]
+    fn retain_range_keeps_window() {
+        let mut tree = demo_tree();
+        assert_eq!(tree.retain_range(&5, &42), 4);
+        assert!(tree.keys().copied().eq([5, 6, 11, 42]));
+        assert_eq!(tree.len(), 4);
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+        assert_navigation(&tree);
+
+        let mut tree = demo_tree();
+        assert_eq!(tree.retain_range(&7, &10), 8);
+        assert!(tree.is_empty());
+
+        let mut tree = demo_tree();
+        assert_eq!(tree.retain_range(&0, &1000), 0);
+        assert_eq!(tree.len(), 8);
+    }
+
+    #[test]
+    fn prune_below_and_above_key() {
+        let mut tree = demo_tree();
+        assert_eq!(tree.prune_below(&11), 4);
+        assert!(tree.keys().copied().eq([11, 42, 65, 123]));
+        assert_eq!(tree.len(), 4);
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+        assert_navigation(&tree);
+
+        assert_eq!(tree.prune_above(&64), 2);
+        assert!(tree.keys().copied().eq([11, 42]));
+        assert_eq!(tree.len(), 2);
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+
+        let mut tree = demo_tree();
+        assert_eq!(tree.prune_above(&5), 5);
+        assert!(tree.keys().copied().eq([1, 3, 5]));
+        assert_eq!(tree.prune_below(&100), 3);
+        assert!(tree.is_empty());
+
+        let mut chain = degenerate_chain(100_000);
+        assert_eq!(chain.prune_below(&99_990), 99_990);
+        assert_eq!(chain.len(), 10);
+        assert_parent_pointers(&chain);
+    }
+
+    #[test]
+    fn take_returns_owned_key() {
+        let mut tree: Tree<String, i32> = Tree::new();
+        for (i, name) in ["beta", "alpha", "gamma", "delta"].into_iter().enumerate() {
+            tree.insert(name.to_string(), i as i32);
+        }
+
+        // The root has two children, so its successor moves into its node.
+        let (key, value): (String, i32) = tree.take("beta").unwrap();
+        assert_eq!((key.as_str(), value), ("beta", 0));
+        assert_eq!(tree.take("alpha"), Some(("alpha".to_string(), 1)));
+        assert_eq!(tree.take("alpha"), None);
+        assert!(tree.keys().map(String::as_str).eq(["delta", "gamma"]));
+        assert_eq!(tree.len(), 2);
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+    }
+
+    #[test]
+    fn get_many_keeps_order_of_keys() {
+        let tree = demo_tree();
+
+        assert_eq!(tree.get_many([&3, &999, &42]), [Some(&3), None, Some(&42)]);
+        assert_eq!(tree.get_many([&42, &3]), [Some(&42), Some(&3)]);
+        assert_eq!(tree.get_many([]), [None::<&i32>; 0]);
+    }
+
+    #[test]
+    fn get_key_value_returns_stored_key() {
+        let mut tree: Tree<String, i32> = Tree::new();
+        tree.insert("alpha".to_string(), 1);
+        tree.insert("beta".to_string(), 2);
+
+        let (key, value) = tree.get_key_value("beta").unwrap();
+        assert_eq!((key.as_str(), *value), ("beta", 2));
+        assert!(ptr::eq(key, tree.keys().nth(1).unwrap()));
+        assert_eq!(tree.get_key_value("gamma"), None);
+    }
+
+    /**
+     * Key, which is ordered by the total order of floats.
+     */
+    #[derive(Debug)]
+    struct TotalF64(f64);
+
+    impl PartialEq for TotalF64 {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(other) == std::cmp::Ordering::Equal
+        }
+    }
+
+    impl Eq for TotalF64 {}
+
+    impl PartialOrd for TotalF64 {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for TotalF64 {
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            self.0.total_cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn keys_are_totally_ordered() {
+        let mut tree = Tree::new();
+        for key in [2.5, f64::NAN, -1.0, 0.0, f64::INFINITY, -0.0] {
+            assert_eq!(tree.insert(TotalF64(key), key), None);
+        }
+
+        assert!(tree.contains(&TotalF64(f64::NAN)));
+        assert!(tree.contains(&TotalF64(-0.0)));
+        assert!(!tree.try_insert(TotalF64(f64::NAN), 1.0));
+
+        let keys: Vec<f64> = tree.iter().map(|(k, _)| k.0).collect();
+        assert_eq!(format!("{:?}", keys), "[-1.0, -0.0, 0.0, 2.5, inf, NaN]");
+    }
+
+    /**
+     * Check, that the cached size of every node matches its subtree.
+     */
+    fn assert_sizes<K, V>(tree: &Tree<K, V>) {
+        let size = tree.fold_subtrees(0, |node, left, right| {
+            assert_eq!(node.size, 1 + left + right);
+            node.size
+        });
+        assert_eq!(size, tree.len());
+    }
+
+    /**
+     * Check, that the parent pointer of every child points to its parent
+     * and the root has no parent.
+     */
+    fn assert_parent_pointers<K, V>(tree: &Tree<K, V>) {
+        let mut stack = Vec::new();
+        if let Some(root) = tree.root.as_deref() {
+            assert_eq!(root.parent, None);
+            stack.push(root);
+        }
+
+        while let Some(node) = stack.pop() {
+            let node_ptr = node as *const Node<K, V> as *mut Node<K, V>;
+            for child in [node.left.as_deref(), node.right.as_deref()]
+                .into_iter()
+                .flatten()
+            {
+                assert_eq!(child.parent, Some(node_ptr));
+                stack.push(child);
+            }
+        }
+    }
+
+    #[test]
+    fn verify_and_repair_fixes_parent_pointers() {
+        let mut tree = demo_tree();
+        assert_eq!(tree.verify_and_repair(), 0);
+
+        tree.find_node_mut(&42).unwrap().parent = None;
+        assert_eq!(tree.successor(&42), None);
+
+        assert_eq!(tree.verify_and_repair(), 1);
+        assert_eq!(tree.successor(&42), Some(&65));
+        assert_parent_pointers(&tree);
+    }
+
+    #[test]
+    fn checked_height_detects_corruption() {
+        let mut tree = demo_tree();
+        assert_eq!(tree.checked_height(), Some(5));
+        assert_eq!(Tree::<i32, i32>::new().checked_height(), Some(0));
+
+        let root: *mut Node<i32, i32> = tree.find_node_mut(&5).unwrap();
+        tree.find_node_mut(&42).unwrap().parent = Some(root);
+        assert_eq!(tree.checked_height(), None);
+
+        tree.verify_and_repair();
+        assert_eq!(tree.checked_height(), Some(5));
+        tree.find_node_mut(&42).unwrap().key = 70;
+        assert_eq!(tree.checked_height(), None);
+    }
+
+    #[test]
+    fn is_valid_bst_detects_misplaced_keys() {
+        let mut tree = demo_tree();
+        assert!(tree.is_valid_bst());
+        assert!(Tree::<i32, i32>::new().is_valid_bst());
+
+        // 42 is in the right subtree of 6, but also in the left subtree of 65.
+        tree.find_node_mut(&42).unwrap().key = 70;
+        assert!(!tree.is_valid_bst());
+    }
+
+    #[test]
+    fn select_and_rank() {
+        let tree = demo_tree();
+        assert_sizes(&tree);
+
+        let keys = [1, 3, 5, 6, 11, 42, 65, 123];
+        for (n, key) in keys.iter().enumerate() {
+            assert_eq!(tree.select(n), Some((key, key)));
+            assert_eq!(tree.rank(key), n);
+        }
+        assert_eq!(tree.select(8), None);
+        assert_eq!(tree.rank(&0), 0);
+        assert_eq!(tree.rank(&7), 4);
+        assert_eq!(tree.rank(&1000), 8);
+    }
+
+    #[test]
+    fn select_and_rank_after_removals() {
+        let mut tree = demo_tree();
+
+        tree.remove(&5);
+        assert_sizes(&tree);
+        tree.remove(&65);
+        assert_sizes(&tree);
+        tree.remove(&1);
+        assert_sizes(&tree);
+        tree.remove(&999);
+        assert_sizes(&tree);
+
+        let keys = [3, 6, 11, 42, 123];
+        for (n, key) in keys.iter().enumerate() {
+            assert_eq!(tree.select(n), Some((key, key)));
+            assert_eq!(tree.rank(key), n);
+        }
+        assert_eq!(tree.select(5), None);
+        assert_eq!(tree.rank(&50), 4);
+    }
+
+    #[test]
+    fn sizes_after_detach_clone_and_entry() {
+        let mut tree = demo_tree();
+
+        tree.detach(6);
+        assert_sizes(&tree);
+        assert_eq!(tree.rank(&123), 4);
+
+        let mut copy = tree.clone();
+        assert_sizes(&copy);
+
+        *copy.entry(100).or_insert(0) += 1;
+        assert_sizes(&copy);
+        assert_eq!(copy.select(4), Some((&100, &1)));
+    }
+
+    /**
+     * Walk the tree with `successor` and `predecessor`, which follow
+     * parent pointers, and compare with the in-order iterator.
+     */
+    fn assert_navigation(tree: &Tree<i32, i32>) {
+        let keys: Vec<i32> = tree.iter().map(|(k, _)| *k).collect();
+        for pair in keys.windows(2) {
+            assert_eq!(tree.successor(&pair[0]), Some(&pair[1]));
+            assert_eq!(tree.predecessor(&pair[1]), Some(&pair[0]));
+        }
+    }
+
+    #[test]
+    fn rotations_keep_order_and_pointers() {
+        let mut tree = demo_tree();
+
+        assert!(tree.rotate_left(&5));
+        assert_eq!(tree.root.as_ref().map(|node| node.key), Some(65));
+        assert!(tree.rotate_right(&65));
+        assert_eq!(tree.root.as_ref().map(|node| node.key), Some(5));
+        assert!(tree.rotate_left(&6));
+        assert!(tree.rotate_right(&65));
+
+        assert!(!tree.rotate_left(&123));
+        assert!(!tree.rotate_right(&1));
+        assert!(!tree.rotate_left(&7));
+
+        assert!(tree.iter().eq(demo_tree().iter()));
+        assert!(tree.is_valid_bst());
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+        assert_navigation(&tree);
+    }
+
+    #[test]
+    fn compact_keeps_entries_in_order() {
+        let mut tree = demo_tree();
+        tree.remove(&6);
+        tree.insert(7, 70);
+
+        tree.compact();
+        assert!(tree.iter().eq(
+            tree! { 1 => 1, 3 => 3, 5 => 5, 7 => 70, 11 => 11, 42 => 42, 65 => 65, 123 => 123 }
+                .iter()
+        ));
+        assert_eq!(tree.height(), 4);
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+        assert_navigation(&tree);
+
+        let mut chain = degenerate_chain(100_000);
+        chain.compact();
+        assert!(chain.keys().copied().eq(0..100_000));
+        assert_eq!(chain.height(), 17);
+    }
+
+    #[test]
+    fn shrink_to_balanced_rebuilds_only_high_trees() {
+        let mut tree = Tree::from_sorted((0..1_000).map(|i| (i, i)).collect());
+        assert!(!tree.shrink_to_balanced());
+        assert!(!demo_tree().shrink_to_balanced());
+        assert!(!Tree::<i32, i32>::new().shrink_to_balanced());
+
+        tree = degenerate_chain(1_000);
+        assert!(tree.shrink_to_balanced());
+        assert_eq!(tree.height(), 10);
+        assert!(tree.keys().copied().eq(0..1_000));
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+        assert!(!tree.shrink_to_balanced());
+    }
+
+    #[test]
+    fn rebalance_degenerate_chain() {
+        let mut tree = Tree::new();
+        for i in 0..1_000 {
+            tree.insert(i, i);
+        }
+        assert_eq!(tree.height(), 1_000);
+
+        tree.rebalance();
+        assert!(tree.is_balanced());
+        assert_eq!(tree.height(), 10);
+        assert_eq!(tree.len(), 1_000);
+        assert!(tree.iter().map(|(k, _)| *k).eq(0..1_000));
+        assert!(tree.is_valid_bst());
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+        assert_navigation(&tree);
+    }
+
+    #[test]
+    fn from_sorted_builds_balanced_tree() {
+        let tree = Tree::from_sorted((0..1023).map(|i| (i, i * 10)).collect());
+
+        assert_eq!(tree.len(), 1023);
+        assert_eq!(tree.height(), 10);
+        assert!(tree
+            .iter()
+            .map(|(k, v)| (*k, *v))
+            .eq((0..1023).map(|i| (i, i * 10))));
+        assert!(tree.is_valid_bst());
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+        assert_navigation(&tree);
+        assert!(Tree::<i32, i32>::from_sorted(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn from_sorted_iter_builds_balanced_tree() {
+        let tree = Tree::from_sorted_iter((0..=1022).map(|i| (i, i)));
+
+        assert_eq!(tree.len(), 1023);
+        assert_eq!(tree.height(), 10);
+        assert!(tree.keys().copied().eq(0..=1022));
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+
+        let map: BTreeMap<_, _> = [(3, "c"), (1, "a"), (2, "b")].into_iter().collect();
+        let tree = Tree::from_sorted_iter(map);
+        assert_eq!(
+            tree.to_structure_string(),
+            "(2: \"b\" (1: \"a\") (3: \"c\"))"
+        );
+    }
+
+    #[test]
+    fn from_unsorted_unique_sorts_and_checks_keys() {
+        let pairs: Vec<(i32, i32)> = (0..1_000).map(|i| ((i * 389) % 1_000, i)).collect();
+        let tree = Tree::from_unsorted_unique(pairs).unwrap();
+        assert!(tree.keys().copied().eq(0..1_000));
+        assert_eq!(tree.height(), 10);
+        assert_eq!(tree.get(&389), Some(&1));
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+
+        let pairs = vec![(5, 'a'), (3, 'b'), (65, 'c'), (3, 'd')];
+        assert_eq!(Tree::from_unsorted_unique(pairs), Err(DuplicateKey(3)));
+        assert_eq!(DuplicateKey(3).to_string(), "key 3 is duplicated");
+    }
+
+    #[test]
+    fn try_from_sorted_reports_first_bad_pair() {
+        let tree = Tree::try_from_sorted(vec![(1, 'a'), (2, 'b'), (3, 'c')]).unwrap();
+        assert_eq!(tree.height(), 2);
+        assert!(Tree::<i32, ()>::try_from_sorted(Vec::new())
+            .unwrap()
+            .is_empty());
+
+        let error = Tree::try_from_sorted(vec![(1, 'a'), (3, 'b'), (2, 'c'), (0, 'd')]);
+        assert_eq!(error, Err(SortedError::OutOfOrder { index: 2, key: 2 }));
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "key 2 at index 2 is out of order"
+        );
+
+        let error = Tree::try_from_sorted(vec![(1, 'a'), (2, 'b'), (2, 'c')]);
+        assert_eq!(error, Err(SortedError::Duplicate { index: 2, key: 2 }));
+    }
+
+    #[test]
+    #[should_panic(expected = "sorted")]
+    #[cfg(debug_assertions)]
+    fn from_sorted_rejects_unsorted_input() {
+        Tree::from_sorted(vec![(2, 2), (1, 1)]);
+    }
+
+    #[test]
+    fn iter_by_value_orders_by_value_then_key() {
+        let tree = tree! { 1 => 5, 2 => 3, 3 => 6 };
+        assert!(tree.iter_by_value().map(|(k, _)| *k).eq([2, 1, 3]));
+
+        let tree = tree! { 4 => 'b', 1 => 'b', 3 => 'a', 2 => 'c' };
+        assert!(tree.iter_by_value().map(|(k, v)| (*k, *v)).eq([
+            (3, 'a'),
+            (1, 'b'),
+            (4, 'b'),
+            (2, 'c')
+        ]));
+    }
+
+    #[test]
+    fn keys_and_values_in_key_order() {
+        let mut tree = demo_tree();
+        for value in tree.values_mut() {
+            *value *= 10;
+        }
+
+        assert!(tree.keys().copied().eq([1, 3, 5, 6, 11, 42, 65, 123]));
+        assert!(tree
+            .values()
+            .copied()
+            .eq([10, 30, 50, 60, 110, 420, 650, 1230]));
+        assert_eq!(tree.keys().next_back(), Some(&123));
+    }
+
+    #[test]
+    fn into_sorted_vec_of_demo_tree() {
+        assert_eq!(
+            demo_tree().into_sorted_vec(),
+            vec![
+                (1, 1),
+                (3, 3),
+                (5, 5),
+                (6, 6),
+                (11, 11),
+                (42, 42),
+                (65, 65),
+                (123, 123)
+            ]
+        );
+
+        let entries = degenerate_chain(100_000).into_sorted_vec();
+        assert!(entries.iter().map(|(k, _)| *k).eq(0..100_000));
+    }
+
+    #[test]
+    fn btree_map_round_trip() {
+        let map: BTreeMap<i32, String> = (0..100).map(|i| (i * 3, i.to_string())).collect();
+
+        let tree = Tree::from(map.clone());
+        assert_eq!(tree.len(), 100);
+        assert!(tree.is_balanced());
+        assert_sizes(&tree);
+        assert_eq!(tree.to_btree_map(), map);
+    }
+
+    #[test]
+    fn tree_macro_inserts_pairs() {
+        let tree =
+            tree! { 5 => 5, 3 => 3, 65 => 65, 123 => 123, 6 => 6, 11 => 11, 1 => 1, 42 => 42 };
+        assert_eq!(tree, demo_tree());
+    }
+
+    #[test]
+    fn depth_of_keys() {
+        let tree = demo_tree();
+
+        assert_eq!(tree.depth_of(&5), Some(0));
+        assert_eq!(tree.depth_of(&3), Some(1));
+        assert_eq!(tree.depth_of(&65), Some(1));
+        assert_eq!(tree.depth_of(&6), Some(2));
+        assert_eq!(tree.depth_of(&42), Some(4));
+        assert_eq!(
+            tree.depth_of(&42).map(|depth| depth + 1),
+            Some(tree.height())
+        );
+        assert_eq!(tree.depth_of(&7), None);
+        assert_eq!(Tree::<i32, i32>::new().depth_of(&7), None);
+    }
+
+    #[test]
+    fn detach_and_find_reach_every_key() {
+        let keys = [1, 3, 5, 6, 11, 42, 65, 123];
+
+        for key in keys {
+            let mut tree = demo_tree();
+            for other in keys {
+                assert!(tree.find(other).is_some(), "key {} is lost", other);
+            }
+
+            let detached = tree.detach(key).expect("key is not detached");
+            let root = detached.root.as_deref().unwrap();
+            assert_eq!(root.key, key);
+            assert_eq!(root.parent, None);
+            assert_parent_pointers(&detached);
+            assert_parent_pointers(&tree);
+            assert_navigation(&tree);
+            assert_eq!(tree.len() + detached.len(), keys.len());
+            assert!(tree.find(key).is_none());
+            assert!(tree.is_valid_bst());
+            assert_sizes(&tree);
+        }
+
+        let mut tree = demo_tree();
+        assert!(tree.detach(7).is_none());
+        assert!(tree.detach(200).is_none());
+        assert_eq!(tree.len(), keys.len());
+    }
+
+    #[test]
+    fn try_insert_keeps_existing_value() {
+        let mut tree = demo_tree();
+
+        assert!(!tree.try_insert(6, 600));
+        assert_eq!(tree.get(&6), Some(&6));
+        assert!(tree.try_insert(7, 700));
+        assert_eq!(tree.get(&7), Some(&700));
+        assert_eq!(tree.len(), 9);
+        assert_sizes(&tree);
+    }
+
+    #[test]
+    fn duplicate_inserts_are_counted() {
+        let mut tree = demo_tree();
+        assert_eq!(tree.duplicates_rejected(), 2);
+
+        assert!(!tree.try_insert(6, 600));
+        assert!(tree.try_insert(7, 700));
+        assert_eq!(tree.duplicates_rejected(), 3);
+        assert_eq!(Tree::<i32, i32>::new().duplicates_rejected(), 0);
+    }
+
+    #[test]
+    fn insert_replaces_value() {
+        let mut tree = demo_tree();
+
+        assert_eq!(tree.insert(6, 600), Some(6));
+        assert_eq!(tree.get(&6), Some(&600));
+        assert_eq!(tree.insert(7, 700), None);
+        assert_eq!(tree.get(&7), Some(&700));
+        assert_eq!(tree.len(), 9);
+        assert_sizes(&tree);
+    }
+
+    #[test]
+    fn index_by_key() {
+        let tree = demo_tree();
+        assert_eq!(tree[&42], 42);
+    }
+
+    #[test]
+    #[should_panic(expected = "key is not found")]
+    fn index_by_missing_key_panics() {
+        let tree = demo_tree();
+        let _ = tree[&7];
+    }
+
+    #[test]
+    fn pop_min_drains_in_ascending_order() {
+        let mut tree = demo_tree();
+
+        let mut keys = Vec::new();
+        while let Some((key, value)) = tree.pop_min() {
+            assert_eq!(key, value);
+            assert!(tree.is_valid_bst());
+            assert_parent_pointers(&tree);
+            assert_sizes(&tree);
+            keys.push(key);
+        }
+        assert_eq!(keys, vec![1, 3, 5, 6, 11, 42, 65, 123]);
+        assert!(tree.is_empty());
+        assert!(tree.root.is_none());
+    }
+
+    #[test]
+    fn remove_min_streams_ascending_keys() {
+        let mut tree: Tree<i32, i32> = (0..100).map(|i| ((i * 37) % 100, i)).collect();
+        for expected in 0..100 {
+            assert_eq!(tree.remove_min().map(|(key, _)| key), Some(expected));
+            if expected % 10 == 0 {
+                assert_parent_pointers(&tree);
+                assert_sizes(&tree);
+            }
+        }
+        assert_eq!(tree.remove_min(), None);
+        assert_eq!(tree.remove_max(), None);
+        assert!(tree.is_empty());
+
+        // Each node of a descending run is the left child of the previous one.
+        let mut chain = Tree::new();
+        for i in (0..10_000).rev() {
+            chain.insert(i, i);
+        }
+        assert_eq!(chain.remove_min(), Some((0, 0)));
+        assert_eq!(chain.remove_max(), Some((9_999, 9_999)));
+        assert_eq!(chain.remove_max(), Some((9_998, 9_998)));
+        assert_eq!(chain.len(), 9_997);
+        assert_parent_pointers(&chain);
+        assert_sizes(&chain);
+    }
+
+    #[test]
+    fn pop_max_drains_in_descending_order() {
+        let mut tree = demo_tree();
+
+        let mut keys = Vec::new();
+        while let Some((key, _)) = tree.pop_max() {
+            assert_parent_pointers(&tree);
+            assert_sizes(&tree);
+            keys.push(key);
+        }
+        assert_eq!(keys, vec![123, 65, 42, 11, 6, 5, 3, 1]);
+        assert!(tree.is_empty());
+        assert_eq!(tree.pop_max(), None);
+    }
+
+    #[test]
+    fn retain_even_values() {
+        let mut tree = demo_tree();
+
+        assert_eq!(tree.retain(|_, v| *v % 2 == 0), 6);
+        assert!(tree.keys().copied().eq([6, 42]));
+        assert_eq!(tree.len(), 2);
+        assert!(tree.is_valid_bst());
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+
+        assert_eq!(tree.retain(|_, _| false), 2);
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn dedup_values_keeps_run_starts() {
+        let mut tree: Tree<i32, i32> = [1, 1, 2, 2, 2, 3]
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| (i as i32 * 10, value))
+            .collect();
+
+        assert_eq!(tree.dedup_values(), 3);
+        assert!(tree.iter().eq([(&0, &1), (&20, &2), (&50, &3)]));
+        assert_eq!(tree.len(), 3);
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+
+        assert_eq!(tree.dedup_values(), 0);
+        assert_eq!(Tree::<i32, i32>::new().dedup_values(), 0);
+    }
+
+    #[test]
+    fn extract_if_returns_removed_entries() {
+        let mut tree = demo_tree();
+
+        let odd = tree.extract_if(|_, v| *v % 2 == 1);
+        assert_eq!(
+            odd,
+            [(1, 1), (3, 3), (5, 5), (11, 11), (65, 65), (123, 123)]
+        );
+        assert!(tree.keys().copied().eq([6, 42]));
+        assert_eq!(tree.len(), 2);
+        assert!(tree.is_valid_bst());
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+
+        assert!(tree.extract_if(|_, _| false).is_empty());
+        assert_eq!(tree.extract_if(|_, _| true).len(), 2);
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn intersection_keeps_common_keys() {
+        let own = tree! { 1 => "a", 3 => "b", 5 => "c", 6 => "d" };
+        let other = tree! { 5 => "x", 6 => "y", 7 => "z" };
+
+        let common = own.intersection(&other);
+        assert!(common.iter().eq([(&5, &"c"), (&6, &"d")]));
+        assert_eq!(common.len(), 2);
+        assert!(common.is_balanced());
+        assert!(own.intersection(&Tree::new()).is_empty());
+    }
+
+    #[test]
+    fn union_prefers_own_values() {
+        let own = tree! { 1 => "a", 3 => "b" };
+        let other = tree! { 3 => "x", 5 => "y" };
+
+        let all = own.union(&other);
+        assert!(all.iter().eq([(&1, &"a"), (&3, &"b"), (&5, &"y")]));
+        assert_eq!(all.len(), 3);
+        assert!(all.is_balanced());
+        assert_eq!(Tree::new().union(&other), other);
+    }
+
+    #[test]
+    fn difference_removes_keys_of_other_tree() {
+        let own = tree! { 1 => "a", 3 => "b", 5 => "c" };
+
+        let rest = own.difference(&tree! { 3 => "x", 7 => "y" });
+        assert!(rest.iter().eq([(&1, &"a"), (&5, &"c")]));
+        assert_eq!(rest.len(), 2);
+        assert_eq!(own.difference(&Tree::new()), own);
+        assert!(own.difference(&own).is_empty());
+    }
+
+    #[test]
+    fn symmetric_difference_keeps_keys_of_one_tree() {
+        let own = tree! { 1 => "a", 2 => "b", 3 => "c" };
+        let other = tree! { 2 => "x", 3 => "y", 4 => "z" };
+
+        let odd = own.symmetric_difference(&other);
+        assert!(odd.iter().eq([(&1, &"a"), (&4, &"z")]));
+        assert_eq!(odd.len(), 2);
+        assert_eq!(own.symmetric_difference(&Tree::new()), own);
+        assert!(own.symmetric_difference(&own).is_empty());
+    }
+
+    #[test]
+    fn append_attaches_greater_keys() {
+        let mut tree = tree! { 1 => 1, 3 => 3, 5 => 5 };
+        let mut other = tree! { 11 => 11, 42 => 42 };
+
+        tree.append(&mut other);
+        assert!(tree.keys().copied().eq([1, 3, 5, 11, 42]));
+        assert_eq!(tree.len(), 5);
+        assert!(other.is_empty());
+        assert_eq!(other.iter().count(), 0);
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+        assert_navigation(&tree);
+
+        let mut empty = Tree::new();
+        empty.append(&mut tree);
+        assert_eq!(empty.len(), 5);
+        empty.append(&mut tree);
+        assert_eq!(empty.len(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "keys of the appended tree must be greater")]
+    #[cfg(debug_assertions)]
+    fn append_checks_order_of_keys() {
+        let mut tree = tree! { 1 => 1, 3 => 3, 5 => 5 };
+        tree.append(&mut tree! { 4 => 4 });
+    }
+
+    #[test]
+    fn merge_disjoint_and_overlapping_trees() {
+        let mut tree: Tree<i32, i32> = (0..50).map(|i| (i * 2, i)).collect();
+        let other: Tree<i32, i32> = (0..50).map(|i| (i * 2 + 1, i)).collect();
+
+        tree.merge(other);
+        assert_eq!(tree.len(), 100);
+        assert!(tree.keys().copied().eq(0..100));
+        assert!(tree.is_balanced());
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+
+        tree.merge(tree! { 5 => 500, 200 => 2000 });
+        assert_eq!(tree.len(), 101);
+        assert_eq!(tree.get(&5), Some(&500));
+        assert_eq!(tree.get(&200), Some(&2000));
+    }
+
+    #[test]
+    fn merge_balanced_of_degenerate_trees() {
+        let tree = degenerate_chain(5_000);
+        let mut other = Tree::new();
+        for i in 2_500..7_500 {
+            other.insert(i, -i);
+        }
+        assert_eq!(other.height(), 5_000);
+
+        let merged = tree.merge_balanced(other);
+        assert_eq!(merged.len(), 7_500);
+        assert!(merged.keys().copied().eq(0..7_500));
+        assert_eq!(merged.height(), 13);
+        assert_eq!(merged.get(&2_500), Some(&2_500));
+        assert_eq!(merged.get(&5_000), Some(&-5_000));
+        assert_parent_pointers(&merged);
+        assert_sizes(&merged);
+    }
+
+    #[test]
+    fn split_off_keeps_smaller_keys() {
+        let mut tree = demo_tree();
+        let rest = tree.split_off(&11);
+
+        assert!(tree.keys().copied().eq([1, 3, 5, 6]));
+        assert!(rest.keys().copied().eq([11, 42, 65, 123]));
+        for tree in [&tree, &rest] {
+            assert_eq!(tree.len(), 4);
+            assert_parent_pointers(tree);
+            assert_sizes(tree);
+        }
+        assert_eq!(tree.duplicates_rejected(), 2);
+        assert_eq!(rest.duplicates_rejected(), 0);
+
+        assert!(tree.split_off(&100).is_empty());
+        assert_eq!(tree.split_off(&0).len(), 4);
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn split_at_pivot() {
+        let (less, rest) = demo_tree().split(&11);
+
+        assert!(less.keys().copied().eq([1, 3, 5, 6]));
+        assert!(rest.keys().copied().eq([11, 42, 65, 123]));
+        for tree in [&less, &rest] {
+            assert_eq!(tree.len(), 4);
+            assert_parent_pointers(tree);
+            assert_sizes(tree);
+        }
+
+        let (less, rest) = demo_tree().split(&0);
+        assert!(less.is_empty());
+        assert_eq!(rest, demo_tree());
+    }
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn equal_trees_of_different_shape_have_equal_hashes() {
+        let tree = demo_tree();
+        let balanced = Tree::from_sorted(demo_tree().into_sorted_vec());
+        assert_ne!(tree.height(), balanced.height());
+
+        assert_eq!(hash_of(&tree), hash_of(&balanced));
+        assert_ne!(hash_of(&tree), hash_of(&tree! { 1 => 1 }));
+    }
+
+    #[test]
+    fn equal_trees_in_hash_set() {
+        let mut set = std::collections::HashSet::new();
+
+        assert!(set.insert(demo_tree()));
+        assert!(!set.insert(Tree::from_sorted(demo_tree().into_sorted_vec())));
+        assert!(set.insert(tree! { 1 => 1 }));
+        assert!(set.contains(&demo_tree()));
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn lowest_common_ancestor_of_keys() {
+        let tree = demo_tree();
+
+        assert_eq!(tree.lowest_common_ancestor(&1, &6), Some(&5));
+        assert_eq!(tree.lowest_common_ancestor(&42, &123), Some(&65));
+        assert_eq!(tree.lowest_common_ancestor(&11, &42), Some(&11));
+        assert_eq!(tree.lowest_common_ancestor(&3, &3), Some(&3));
+        assert_eq!(tree.lowest_common_ancestor(&1, &7), None);
+    }
+
+    #[test]
+    fn path_to_keys() {
+        let tree = demo_tree();
+
+        assert_eq!(tree.path_to(&1), Some(vec![&5, &3, &1]));
+        assert_eq!(tree.path_to(&42), Some(vec![&5, &65, &6, &11, &42]));
+        assert_eq!(tree.path_to(&5), Some(vec![&5]));
+        assert_eq!(tree.path_to(&7), None);
+    }
+
+    #[test]
+    fn parent_key_of_root_child_and_missing_key() {
+        let tree = demo_tree();
+        assert_eq!(tree.parent_key(&1), Some(Some(&3)));
+        assert_eq!(tree.parent_key(&42), Some(Some(&11)));
+        assert_eq!(tree.parent_key(&65), Some(Some(&5)));
+        assert_eq!(tree.parent_key(&5), Some(None));
+        assert_eq!(tree.parent_key(&7), None);
+    }
+
+    #[test]
+    fn children_keys_of_nodes() {
+        let tree = demo_tree();
+        assert_eq!(tree.children_keys(&5), Some((Some(&3), Some(&65))));
+        assert_eq!(tree.children_keys(&3), Some((Some(&1), None)));
+        assert_eq!(tree.children_keys(&6), Some((None, Some(&11))));
+        assert_eq!(tree.children_keys(&123), Some((None, None)));
+        assert_eq!(tree.children_keys(&7), None);
+    }
+
+    #[test]
+    fn position_of_is_inverse_of_nth() {
+        let tree = demo_tree();
+        assert_eq!(tree.position_of(&6), Some(3));
+        assert_eq!(tree.position_of(&1), Some(0));
+        assert_eq!(tree.position_of(&123), Some(7));
+        assert_eq!(tree.position_of(&7), None);
+        for (position, (key, _)) in tree.iter().enumerate() {
+            assert_eq!(tree.position_of(key), Some(position));
+            assert_eq!(tree.nth(position).map(|(k, _)| k), Some(key));
+        }
+    }
+
+    #[test]
+    fn count_leaves_and_internal_nodes() {
+        let tree = demo_tree();
+
+        assert_eq!(tree.count_leaves(), 3);
+        assert_eq!(tree.count_internal(), 5);
+        assert_eq!(tree.count_leaves() + tree.count_internal(), tree.len());
+
+        let tree = degenerate_chain(100_000);
+        assert_eq!(tree.count_leaves(), 1);
+        assert_eq!(tree.count_internal(), 99_999);
+    }
+
+    #[test]
+    fn iter_leaves_of_demo_tree() {
+        let tree = demo_tree();
+        assert!(tree.iter_leaves().map(|(k, _)| *k).eq([1, 42, 123]));
+        assert_eq!(tree.iter_leaves().count(), tree.count_leaves());
+
+        assert!(degenerate_chain(1_000)
+            .iter_leaves()
+            .map(|(k, _)| *k)
+            .eq([999]));
+        assert_eq!(Tree::<i32, i32>::new().iter_leaves().next(), None);
+    }
+
+    #[test]
+    fn invert_mirrors_the_tree() {
+        let mut tree = demo_tree();
+
+        tree.invert();
+        assert!(tree.keys().copied().eq([123, 65, 42, 11, 6, 5, 3, 1]));
+        assert!(!tree.is_valid_bst());
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+
+        tree.invert();
+        assert_eq!(tree, demo_tree());
+        assert!(tree.is_valid_bst());
+        assert_eq!(tree.get(&42), Some(&42));
+    }
+
+    #[test]
+    fn structurally_equal_compares_shape() {
+        let tree: Tree<i32, i32> = [3, 1, 2].into_iter().map(|i| (i, i)).collect();
+        let other: Tree<i32, i32> = [3, 2, 1].into_iter().map(|i| (i, i)).collect();
+
+        assert_eq!(tree, other);
+        assert!(!tree.structurally_equal(&other));
+        assert!(tree.structurally_equal(&tree.clone()));
+        assert!(demo_tree().structurally_equal(&demo_tree()));
+        assert!(!demo_tree().structurally_equal(&Tree::new()));
+    }
+
+    #[test]
+    fn shape_signature_ignores_keys_and_values() {
+        let tree = demo_tree();
+        let shifted: Tree<i32, &str> = [50, 30, 650, 1230, 60, 110, 10, 420]
+            .into_iter()
+            .map(|key| (key, "value"))
+            .collect();
+        assert_eq!(tree.shape_signature(), shifted.shape_signature());
+        assert_eq!(tree.shape_signature(), tree.clone().shape_signature());
+
+        let mut other = demo_tree();
+        other.insert(7, 7);
+        assert_ne!(tree.shape_signature(), other.shape_signature());
+
+        // Same number of nodes, mirrored shapes.
+        let left: Tree<i32, i32> = [2, 1].into_iter().map(|i| (i, i)).collect();
+        let right: Tree<i32, i32> = [1, 2].into_iter().map(|i| (i, i)).collect();
+        assert_ne!(left.shape_signature(), right.shape_signature());
+        assert_ne!(
+            Tree::<i32, i32>::new().shape_signature(),
+            left.shape_signature()
+        );
+    }
+
+    #[test]
+    fn contains_subtree_matches_whole_subtrees() {
+        let tree = demo_tree();
+
+        assert!(tree.contains_subtree(&tree! { 11 => 11, 42 => 42 }));
+        assert!(tree.contains_subtree(&tree! { 3 => 3, 1 => 1 }));
+        assert!(tree.contains_subtree(&tree.clone()));
+        assert!(tree.contains_subtree(&Tree::new()));
+
+        // 6 has the right subtree of two nodes, not of one.
+        assert!(!tree.contains_subtree(&tree! { 6 => 6, 11 => 11 }));
+        assert!(!tree.contains_subtree(&tree! { 11 => 0, 42 => 42 }));
+        assert!(!tree.contains_subtree(&tree! { 42 => 42, 11 => 11 }));
+    }
+
+    #[test]
+    fn tree_moves_to_another_thread() {
+        let tree = demo_tree();
+
+        let handle = std::thread::spawn(move || {
+            let mut tree = tree;
+            tree.insert(7, 7);
+            tree
+        });
+        let tree = handle.join().unwrap();
+        assert_eq!(tree.successor(&6), Some(&7));
+
+        std::thread::scope(|scope| {
+            let first = scope.spawn(|| tree.iter().count());
+            let second = scope.spawn(|| tree.predecessor(&7).copied());
+            assert_eq!(first.join().unwrap(), 9);
+            assert_eq!(second.join().unwrap(), Some(6));
+        });
+    }
+
+    #[test]
+    fn rank_range_gives_positions() {
+        let tree = demo_tree();
+        let keys: Vec<i32> = tree.keys().copied().collect();
+
+        assert_eq!(tree.rank_range(&5, &11), 2..5);
+        assert_eq!(keys[tree.rank_range(&5, &11)], [5, 6, 11]);
+        assert_eq!(tree.rank_range(&4, &12), 2..5);
+        assert_eq!(tree.rank_range(&0, &1000), 0..8);
+        assert_eq!(tree.rank_range(&7, &10), 4..4);
+        assert!(tree.rank_range(&11, &5).is_empty());
+    }
+
+    #[test]
+    fn count_range_of_keys() {
+        let tree = demo_tree();
+
+        assert_eq!(tree.count_range(&5, &42), 4);
+        assert_eq!(tree.count_range(&4, &41), 3);
+        assert_eq!(tree.count_range(&0, &1000), 8);
+        assert_eq!(tree.count_range(&7, &10), 0);
+        assert_eq!(tree.count_range(&42, &5), 0);
+        assert_eq!(tree.count_range(&6, &6), 1);
+        for (low, high) in [(1, 123), (3, 64), (12, 200)] {
+            assert_eq!(
+                tree.count_range(&low, &high),
+                tree.range(&low, &high).count()
+            );
+        }
+    }
+
+    #[test]
+    fn closest_by_distance() {
+        let tree = demo_tree();
+        let dist = |a: &i32, b: &i32| (a - b).abs();
+
+        assert_eq!(tree.closest_by(&7, dist), Some((&6, &6)));
+        assert_eq!(tree.closest_by(&40, dist), Some((&42, &42)));
+        assert_eq!(tree.closest_by(&42, dist), Some((&42, &42)));
+        assert_eq!(tree.closest_by(&4, dist), Some((&3, &3)));
+        assert_eq!(tree.closest_by(&-10, dist), Some((&1, &1)));
+        assert_eq!(tree.closest_by(&1_000, dist), Some((&123, &123)));
+
+        let empty: Tree<i32, i32> = Tree::new();
+        assert_eq!(empty.closest_by(&7, dist), None);
+    }
+
+    #[test]
+    fn nth_entry_in_sorted_order() {
+        let tree = demo_tree();
+
+        assert_eq!(tree.nth(0), tree.min());
+        assert_eq!(tree.nth(tree.len() - 1), tree.max());
+        assert_eq!(tree.nth(3), Some((&6, &6)));
+        assert_eq!(tree.nth(tree.len()), None);
+    }
+
+    #[test]
+    fn kth_smallest_and_largest() {
+        let tree = demo_tree();
+
+        assert_eq!(tree.kth_smallest(3), Some((&5, &5)));
+        assert_eq!(tree.kth_largest(1), Some((&123, &123)));
+        assert_eq!(tree.kth_smallest(1), tree.min());
+        assert_eq!(tree.kth_largest(8), tree.min());
+        assert_eq!(tree.kth_smallest(0), None);
+        assert_eq!(tree.kth_largest(0), None);
+        assert_eq!(tree.kth_smallest(9), None);
+        assert_eq!(tree.kth_largest(9), None);
+    }
+
+    #[test]
+    fn get_or_insert_with_calls_function_only_for_missing_key() {
+        let mut tree = demo_tree();
+        let mut calls = 0;
+
+        *tree.get_or_insert_with(7, || {
+            calls += 1;
+            70
+        }) += 1;
+        assert_eq!(calls, 1);
+        assert_eq!(tree.get(&7), Some(&71));
+
+        assert_eq!(
+            *tree.get_or_insert_with(6, || {
+                calls += 1;
+                60
+            }),
+            6
+        );
+        assert_eq!(calls, 1);
+        assert_eq!(tree.len(), 9);
+    }
+
+    #[test]
+    fn get_or_insert_keeps_first_value() {
+        let mut tree = Tree::new();
+        assert_eq!(*tree.get_or_insert(7, 70), 70);
+        *tree.get_or_insert(7, 700) += 1;
+        assert_eq!(*tree.get_or_insert(7, 7_000), 71);
+        assert_eq!(tree.len(), 1);
+    }
+
+    #[test]
+    fn entry_or_default_counts_repeats() {
+        let mut counts = Tree::new();
+        for word in ["b", "a", "c", "a", "b", "a"] {
+            *counts.entry_or_default(word) += 1;
+        }
+
+        assert!(counts.iter().eq([(&"a", &3), (&"b", &2), (&"c", &1)]));
+        assert_eq!(counts.len(), 3);
+        assert_sizes(&counts);
+    }
+
+    #[test]
+    fn replace_value_never_inserts() {
+        let mut tree = demo_tree();
+
+        assert_eq!(tree.replace_value(&6, 600), Some(6));
+        assert_eq!(tree.get(&6), Some(&600));
+
+        assert_eq!(tree.replace_value(&7, 700), None);
+        assert!(!tree.contains(&7));
+        assert_eq!(tree.len(), 8);
+    }
+
+    #[test]
+    fn find_by_value_returns_smallest_key() {
+        let mut tree = demo_tree();
+        assert_eq!(tree.find_by_value(&42), Some(&42));
+        assert_eq!(tree.find_by_value(&7), None);
+
+        tree.insert(65, 42);
+        tree.insert(0, 42);
+        assert_eq!(tree.find_by_value(&42), Some(&0));
+    }
+
+    #[test]
+    fn map_values_keeps_shape() {
+        let tree = demo_tree();
+
+        let mapped = tree.map_values(|v| v * 10);
+        assert_eq!(mapped.get(&6), Some(&60));
+        assert_eq!(mapped.len(), tree.len());
+        assert!(mapped.structurally_equal(&tree));
+        assert_parent_pointers(&mapped);
+        assert_sizes(&mapped);
+
+        let labels = tree.map_values(|v| format!("#{}", v));
+        assert_eq!(labels.get(&42).map(String::as_str), Some("#42"));
+    }
+
+    #[test]
+    fn fold_and_sum_values() {
+        let tree = demo_tree();
+
+        assert_eq!(tree.sum_values(), 256);
+        assert_eq!(Tree::<i32, i32>::new().sum_values(), 0);
+
+        let keys = tree.fold(Vec::new(), |mut keys, key, _| {
+            keys.push(*key);
+            keys
+        });
+        assert_eq!(keys, vec![1, 3, 5, 6, 11, 42, 65, 123]);
+    }
+
+    #[test]
+    fn detach_subtree_returns_valid_tree() {
+        let mut tree = demo_tree();
+
+        let mut subtree = tree.detach_subtree(&65).unwrap();
+        assert!(subtree.keys().copied().eq([6, 11, 42, 65, 123]));
+        assert!(tree.keys().copied().eq([1, 3, 5]));
+        assert!(!tree.contains(&65) && !tree.contains(&123));
+        assert_eq!(subtree.len() + tree.len(), 8);
+        for part in [&tree, &subtree] {
+            assert!(part.is_valid_bst());
+            assert_parent_pointers(part);
+            assert_sizes(part);
+            assert_navigation(part);
+        }
+
+        subtree.insert(100, 100);
+        assert_eq!(subtree.successor(&65), Some(&100));
+        assert!(tree.detach_subtree(&65).is_none());
+    }
+
+    #[test]
+    fn attach_subtree_restores_detached_subtree() {
+        let mut tree = demo_tree();
+
+        let subtree = tree.detach_subtree(&65).unwrap();
+        assert!(tree.attach_subtree(subtree).is_ok());
+        assert!(tree.structurally_equal(&demo_tree()));
+        assert_eq!(tree.len(), 8);
+        assert_parent_pointers(&tree);
+        assert_sizes(&tree);
+        assert_navigation(&tree);
+
+        let overlapping = tree! { 2 => 2, 7 => 7 };
+        let rejected = tree.attach_subtree(overlapping).unwrap_err();
+        assert_eq!(rejected.len(), 2);
+        assert!(tree.attach_subtree(tree! { 5 => 0 }).is_err());
+        assert!(tree.structurally_equal(&demo_tree()));
+
+        assert!(tree
+            .attach_subtree(tree! { 8 => 8, 7 => 7, 9 => 9 })
+            .is_ok());
+        assert_eq!(tree.path_to(&7), Some(vec![&5, &65, &6, &11, &8, &7]));
+        assert!(tree.is_valid_bst());
+        assert_sizes(&tree);
+
+        let mut empty = Tree::new();
+        assert!(empty.attach_subtree(demo_tree()).is_ok());
+        assert_eq!(empty, demo_tree());
+    }
+
+    #[test]
+    fn count_matching_entries() {
+        let tree = demo_tree();
+
+        assert_eq!(tree.count_matching(|_, v| v % 2 == 0), 2);
+        assert_eq!(tree.count_matching(|k, _| *k > 10), 4);
+        assert_eq!(tree.count_matching(|_, _| true), tree.len());
+    }
+
+    #[test]
+    fn trees_are_ordered_lexicographically() {
+        let first = tree! { 1 => 0, 2 => 0 };
+        let second = tree! { 1 => 0, 3 => 0 };
+
+        assert!(first < second);
+        assert!(tree! { 1 => 0 } < first);
+        assert!(tree! { 1 => 0, 2 => 1 } > first);
+        assert_eq!(
+            first.partial_cmp(&tree! { 2 => 0, 1 => 0 }),
+            Some(Ordering::Equal)
+        );
+
+        let mut trees = vec![second.clone(), first.clone()];
+        trees.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(trees, [first, second]);
+    }
+
+    #[test]
+    fn remove_last_node() {
+        let mut tree = Tree::new();
+        tree.insert(1, "one");
+
+        assert_eq!(tree.remove(&1), Some("one"));
+        assert!(tree.root.is_none());
+        assert_eq!(tree.remove(&1), None);
+        assert!(tree.is_valid_bst());
+        assert_parent_pointers(&tree);
+    }
+
+    /**
+     * SplitMix64 generator, so randomized tests are reproducible by seed.
+     */
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        }
+
+        fn below(&mut self, bound: u64) -> i32 {
+            (self.next() % bound) as i32
+        }
+    }
+
+    /**
+     * Check the tree against the reference map after an operation.
+     */
+    fn assert_matches_reference(tree: &Tree<i32, i32>, reference: &BTreeMap<i32, i32>, step: &str) {
+        assert!(tree.is_valid_bst(), "order is broken after {}", step);
+        assert_eq!(
+            tree.len(),
+            reference.len(),
+            "length is wrong after {}",
+            step
+        );
+        assert!(
+            tree.iter().eq(reference.iter()),
+            "entries differ after {}",
+            step
+        );
+        assert!(
+            tree.checked_height().is_some(),
+            "tree is corrupt after {}",
+            step
+        );
+        assert_parent_pointers(tree);
+        assert_sizes(tree);
+    }
+
+    /**
+     * Random inserts and removes of keys from a small range, so removed
+     * nodes often have two children and removed keys are often missing.
+     */
+    #[test]
+    fn random_removes_match_btree_map() {
+        for seed in 0..200 {
+            let mut random = Random(seed);
+            let mut tree = Tree::new();
+            let mut reference = BTreeMap::new();
+
+            for _ in 0..random.below(64) {
+                let key = random.below(64);
+                assert_eq!(tree.insert(key, key), reference.insert(key, key));
+            }
+            assert_matches_reference(&tree, &reference, "inserts");
+
+            for _ in 0..100 {
+                let key = random.below(64);
+                let step = match random.below(6) {
+                    0 => {
+                        assert_eq!(tree.insert(key, -key), reference.insert(key, -key));
+                        "insert"
+                    }
+                    1 => {
+                        assert_eq!(tree.take(&key), reference.remove_entry(&key));
+                        "take"
+                    }
+                    2 => {
+                        let removed = match tree.entry(key) {
+                            Entry::Occupied(entry) => Some(entry.remove()),
+                            Entry::Vacant(_) => None,
+                        };
+                        assert_eq!(removed, reference.remove(&key));
+                        "entry remove"
+                    }
+                    3 => {
+                        assert_eq!(tree.remove_min(), reference.pop_first());
+                        "remove_min"
+                    }
+                    _ => {
+                        assert_eq!(tree.remove(&key), reference.remove(&key));
+                        "remove"
+                    }
+                };
+                assert_matches_reference(&tree, &reference, step);
+                assert_navigation(&tree);
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Tree, which records keys in the order they were accepted, so the shape
+//! of the tree can be explained and reproduced by replaying the log.
+
+use alloc::vec::Vec;
+
+use crate::{Entry, Tree};
+
+/**
+ * Tree with a log of inserted keys. Only inserts of new keys are
+ * recorded, because replacing a value does not change the shape.
+ */
+#[derive(Debug)]
+pub struct LoggedTree<K, V> {
+    tree: Tree<K, V>,
+    log: Vec<K>,
+}
+
+impl<K, V> LoggedTree<K, V> {
+    /**
+     * Keys of accepted inserts, from the first one to the last one.
+     */
+    pub fn insertion_log(&self) -> &[K] {
+        &self.log
+    }
+
+    /**
+     * The underlying tree for read-only access.
+     */
+    pub fn tree(&self) -> &Tree<K, V> {
+        &self.tree
+    }
+
+    /**
+     * Number of key-value pairs in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /**
+     * Unwrap the tree and the log.
+     */
+    pub fn into_parts(self) -> (Tree<K, V>, Vec<K>) {
+        (self.tree, self.log)
+    }
+}
+
+impl<K, V> LoggedTree<K, V>
+where
+    K: Ord + Clone,
+{
+    /**
+     * Insert key-value pair into tree and record the key, when it is new.
+     * When the key is already present, its value is replaced and
+     * the previous value is returned.
+     */
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.tree.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                self.log.push(entry.key().clone());
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    /**
+     * Get reference to the value stored under the key.
+     */
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.tree.get(key)
+    }
+
+    /**
+     * Check is the key present in the tree.
+     */
+    pub fn contains(&self, key: &K) -> bool {
+        self.tree.contains(key)
+    }
+}
+
+impl<K, V> Tree<K, V>
+where
+    K: Ord + Clone,
+{
+    /**
+     * Create an empty tree, which records keys of accepted inserts.
+     */
+    pub fn new_logged() -> LoggedTree<K, V> {
+        LoggedTree {
+            tree: Tree::new(),
+            log: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Tree;
+
+    #[test]
+    fn log_keeps_distinct_keys_in_acceptance_order() {
+        let mut tree = Tree::new_logged();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i);
+        }
+        assert_eq!(tree.insert(6, 60), Some(6));
+
+        assert_eq!(tree.insertion_log(), [5, 3, 65, 123, 6, 11, 1, 42]);
+        assert_eq!(tree.len(), 8);
+        assert_eq!(tree.get(&6), Some(&60));
+
+        // Replaying the log gives the same shape.
+        let (tree, log) = tree.into_parts();
+        let replayed: Tree<i32, i32> = log.into_iter().map(|key| (key, key)).collect();
+        assert!(tree.structurally_equal(&replayed));
+    }
+}
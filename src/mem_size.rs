@@ -0,0 +1,142 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Estimation of heap memory used by the tree.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+use crate::{Node, Tree};
+
+/**
+ * Size of heap memory owned by a value, not counting the value itself.
+ * Implement it for keys and values, which own heap memory, to include
+ * that memory into `Tree::estimated_memory_bytes_deep`.
+ */
+pub trait MemSize {
+    fn heap_size(&self) -> usize;
+}
+
+macro_rules! impl_mem_size_without_heap {
+    ($($type:ty),*) => {
+        $(
+            impl MemSize for $type {
+                fn heap_size(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+impl_mem_size_without_heap!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64
+);
+
+impl<T: ?Sized> MemSize for &T {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl MemSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: MemSize> MemSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(MemSize::heap_size).sum::<usize>()
+    }
+}
+
+impl<T: MemSize> MemSize for Box<T> {
+    fn heap_size(&self) -> usize {
+        size_of::<T>() + (**self).heap_size()
+    }
+}
+
+impl<T: MemSize> MemSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, MemSize::heap_size)
+    }
+}
+
+impl<K, V> Tree<K, V> {
+    /**
+     * Heap memory used by nodes of the tree, without memory, which keys
+     * and values own themselves. The allocator may use more.
+     */
+    pub fn estimated_memory_bytes(&self) -> usize {
+        self.len * size_of::<Node<K, V>>()
+    }
+}
+
+impl<K, V> Tree<K, V>
+where
+    K: MemSize,
+    V: MemSize,
+{
+    /**
+     * Heap memory used by nodes of the tree, like `estimated_memory_bytes`,
+     * plus heap memory owned by keys and values.
+     */
+    pub fn estimated_memory_bytes_deep(&self) -> usize {
+        let owned: usize = self
+            .iter()
+            .map(|(key, value)| key.heap_size() + value.heap_size())
+            .sum();
+        self.estimated_memory_bytes() + owned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use core::mem::size_of;
+
+    use crate::{Node, Tree};
+
+    #[test]
+    fn shallow_estimate_counts_nodes() {
+        let tree: Tree<i32, i32> = (0..100).map(|i| (i, i)).collect();
+        assert_eq!(
+            tree.estimated_memory_bytes(),
+            100 * size_of::<Node<i32, i32>>()
+        );
+        assert_eq!(
+            tree.estimated_memory_bytes_deep(),
+            tree.estimated_memory_bytes()
+        );
+        assert_eq!(Tree::<i32, i32>::new().estimated_memory_bytes(), 0);
+    }
+
+    #[test]
+    fn deep_estimate_adds_owned_heap() {
+        let mut tree = Tree::new();
+        tree.insert(1, String::with_capacity(10));
+        tree.insert(2, String::with_capacity(30));
+
+        let nodes = 2 * size_of::<Node<i32, String>>();
+        assert_eq!(tree.estimated_memory_bytes(), nodes);
+        assert_eq!(tree.estimated_memory_bytes_deep(), nodes + 40);
+    }
+}
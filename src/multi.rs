@@ -0,0 +1,301 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Binary search tree with duplicate keys. Each inserted entry gets its
+//! own node, and an entry with a key equal to the key of a node goes into
+//! the right subtree of the node, so keys in a left subtree are less than
+//! the key of the node and keys in a right subtree are not less.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+
+use crate::binary_node::{self, BinaryNode, InOrder};
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+#[derive(Debug)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl<K, V> BinaryNode for Node<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn key(&self) -> &K {
+        &self.key
+    }
+
+    fn value(&self) -> &V {
+        &self.value
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+/**
+ * Unbalanced binary search tree, which keeps all entries of equal keys,
+ * like a multiset or a multimap.
+ */
+#[derive(Debug)]
+pub struct MultiTree<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+impl<K, V> Default for MultiTree<K, V> {
+    fn default() -> Self {
+        Self { root: None, len: 0 }
+    }
+}
+
+impl<K, V> Drop for MultiTree<K, V> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<K, V> MultiTree<K, V> {
+    /**
+     * Iterate over references to keys and values in ascending key order.
+     * Entries of equal keys are grouped in the order of insertion.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        InOrder::new(self.root.as_deref())
+    }
+
+    /**
+     * Number of entries in the tree, including duplicates.
+     */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Remove all entries from the tree. Nodes are freed in a loop,
+     * because the default drop of nested boxes recurses once per level.
+     */
+    pub fn clear(&mut self) {
+        let mut stack: Vec<Box<Node<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+        self.len = 0;
+    }
+}
+
+impl<K, V> MultiTree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Insert key-value pair into tree. Entries with the same key are kept,
+     * the new entry goes after them.
+     */
+    pub fn insert(&mut self, key: K, value: V) {
+        let mut slot = &mut self.root;
+        while let Some(node) = slot {
+            slot = match key.cmp(&node.key) {
+                Ordering::Less => &mut node.left,
+                Ordering::Equal | Ordering::Greater => &mut node.right,
+            };
+        }
+
+        *slot = Some(Box::new(Node {
+            key,
+            value,
+            left: None,
+            right: None,
+        }));
+        self.len += 1;
+    }
+
+    /**
+     * Number of entries with the key.
+     */
+    pub fn count<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut count = 0;
+        let mut subtree = self.root.as_deref();
+        while let Some(node) = subtree {
+            subtree = match key.cmp(node.key.borrow()) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Equal => {
+                    count += 1;
+                    node.right.as_deref()
+                }
+                Ordering::Greater => node.right.as_deref(),
+            };
+        }
+        count
+    }
+
+    /**
+     * Check is the key present in the tree.
+     */
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /**
+     * Get reference to the value of the first inserted entry with the key.
+     */
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        binary_node::find(self.root.as_deref(), key).map(BinaryNode::value)
+    }
+
+    /**
+     * Remove the first inserted entry with the key and return its value,
+     * other entries with the key stay in the tree.
+     */
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut slot = &mut self.root;
+        loop {
+            match key.cmp(slot.as_ref()?.key.borrow()) {
+                Ordering::Equal => break,
+                Ordering::Less => slot = &mut slot.as_mut()?.left,
+                Ordering::Greater => slot = &mut slot.as_mut()?.right,
+            }
+        }
+
+        // The successor is not less than the removed key, and keys of the
+        // left subtree are less than it, so the order of the tree is kept.
+        let mut removed_node = slot.take()?;
+        *slot = match (removed_node.left.take(), removed_node.right.take()) {
+            (None, child) | (child, None) => child,
+
+            (Some(left), Some(right)) => {
+                let mut right = Some(right);
+                let mut successor = Self::remove_min(&mut right)?;
+                successor.left = Some(left);
+                successor.right = right;
+                Some(successor)
+            }
+        };
+        self.len -= 1;
+        Some(removed_node.value)
+    }
+
+    /**
+     * Detach the leftmost node of the subtree in the slot.
+     */
+    fn remove_min(mut slot: &mut Link<K, V>) -> Option<Box<Node<K, V>>> {
+        while slot.as_ref()?.left.is_some() {
+            slot = &mut slot.as_mut()?.left;
+        }
+        let mut min_node = slot.take()?;
+        *slot = min_node.right.take();
+        Some(min_node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MultiTree;
+
+    #[test]
+    fn duplicates_are_counted() {
+        let mut tree = MultiTree::new();
+        for (i, key) in [5, 3, 5, 6, 1, 5, 3].into_iter().enumerate() {
+            tree.insert(key, i);
+        }
+
+        assert_eq!(tree.len(), 7);
+        assert_eq!(tree.count(&5), 3);
+        assert_eq!(tree.count(&3), 2);
+        assert_eq!(tree.count(&4), 0);
+        assert!(tree.iter().map(|(k, v)| (*k, *v)).eq([
+            (1, 4),
+            (3, 1),
+            (3, 6),
+            (5, 0),
+            (5, 2),
+            (5, 5),
+            (6, 3)
+        ]));
+    }
+
+    #[test]
+    fn remove_takes_one_occurrence() {
+        let mut tree = MultiTree::new();
+        for (i, key) in [5, 3, 5, 6, 1, 5, 3].into_iter().enumerate() {
+            tree.insert(key, i);
+        }
+
+        // The root has two children and its successor has the same key.
+        assert_eq!(tree.remove(&5), Some(0));
+        assert_eq!(tree.count(&5), 2);
+        assert_eq!(tree.get(&5), Some(&2));
+        assert_eq!(tree.remove(&5), Some(2));
+        assert_eq!(tree.remove(&5), Some(5));
+        assert_eq!(tree.remove(&5), None);
+
+        assert_eq!(tree.len(), 4);
+        assert!(tree.iter().map(|(k, _)| *k).eq([1, 3, 3, 6]));
+        assert!(tree.contains(&3));
+        assert!(!tree.contains(&5));
+    }
+
+    #[test]
+    fn drop_deep_chain_without_stack_overflow() {
+        let thread = std::thread::Builder::new().stack_size(64 * 1024);
+        let handle = thread
+            .spawn(|| {
+                // Equal keys go right, so duplicates build a chain too.
+                let mut tree = MultiTree::new();
+                for i in 0..10_000 {
+                    tree.insert(0, i);
+                }
+                assert_eq!(tree.count(&0), 10_000);
+
+                tree.clear();
+                assert!(tree.is_empty());
+                assert_eq!(tree.get(&0), None);
+
+                for i in 0..10_000 {
+                    tree.insert(i, i);
+                }
+                assert_eq!(tree.len(), 10_000);
+            })
+            .unwrap();
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,88 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Total order of trees, for sorting them and storing them in ordered
+//! collections.
+//!
+//! `Tree` implements `PartialOrd` only, because with `Ord` the prelude
+//! methods `Ord::min` and `Ord::max` would take precedence over the
+//! inherent `min` and `max` on an owned tree.
+
+use core::cmp::Ordering;
+
+use crate::Tree;
+
+/**
+ * Tree, which is ordered lexicographically by its entries in key order,
+ * like `PartialOrd` of `Tree`, but totally, so it can be a key of
+ * `BTreeSet` or `BTreeMap`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OrdTree<K, V>(pub Tree<K, V>);
+
+impl<K, V> OrdTree<K, V> {
+    /**
+     * Unwrap the tree.
+     */
+    pub fn into_inner(self) -> Tree<K, V> {
+        self.0
+    }
+}
+
+impl<K, V> From<Tree<K, V>> for OrdTree<K, V> {
+    fn from(tree: Tree<K, V>) -> Self {
+        OrdTree(tree)
+    }
+}
+
+impl<K, V> PartialOrd for OrdTree<K, V>
+where
+    K: Ord,
+    V: Ord,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K, V> Ord for OrdTree<K, V>
+where
+    K: Ord,
+    V: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().cmp(other.0.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cmp::Ordering;
+    use std::collections::BTreeSet;
+
+    use super::OrdTree;
+    use crate::Tree;
+
+    fn ord_tree(keys: &[i32]) -> OrdTree<i32, i32> {
+        keys.iter()
+            .map(|&key| (key, 0))
+            .collect::<Tree<_, _>>()
+            .into()
+    }
+
+    #[test]
+    fn trees_are_totally_ordered() {
+        let first = ord_tree(&[1, 2]);
+        let second = ord_tree(&[1, 3]);
+
+        assert_eq!(first.cmp(&second), Ordering::Less);
+        assert_eq!(second.cmp(&first), Ordering::Greater);
+        assert_eq!(ord_tree(&[1]).cmp(&first), Ordering::Less);
+        assert_eq!(first.cmp(&ord_tree(&[2, 1])), Ordering::Equal);
+
+        let mut set = BTreeSet::new();
+        set.insert(second.clone());
+        set.insert(first.clone());
+        set.insert(ord_tree(&[2, 1]));
+        assert!(set.into_iter().eq([first, second]));
+    }
+}
@@ -0,0 +1,359 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Persistent binary search tree. Updates do not modify the tree, but
+//! return a new version of it, which shares all unchanged subtrees with
+//! the old version. Only the nodes on the path to the changed key are
+//! copied, so nodes have no parent pointers.
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::binary_node::{self, BinaryNode, InOrder};
+
+type Link<K, V> = Option<Rc<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl<K, V> BinaryNode for Node<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn key(&self) -> &K {
+        &self.key
+    }
+
+    fn value(&self) -> &V {
+        &self.value
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+impl<K, V> Node<K, V> {
+    fn new(key: K, value: V, left: Link<K, V>, right: Link<K, V>) -> Self {
+        Self {
+            key,
+            value,
+            left,
+            right,
+        }
+    }
+}
+
+impl<K, V> Node<K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    /**
+     * Copy of the node, with the child on the side replaced.
+     */
+    fn with_child(&self, side: Ordering, child: Link<K, V>) -> Rc<Node<K, V>> {
+        let (left, right) = match side {
+            Ordering::Less => (child, self.right.clone()),
+            _ => (self.left.clone(), child),
+        };
+        Rc::new(Node::new(self.key.clone(), self.value.clone(), left, right))
+    }
+
+    /**
+     * Copy nodes of the path bottom-up, replacing the child, through which
+     * the path goes, with the new version of the subtree below.
+     */
+    fn rebuild_path(path: Vec<(&Node<K, V>, Ordering)>, mut subtree: Link<K, V>) -> Link<K, V> {
+        for (node, side) in path.into_iter().rev() {
+            subtree = Some(node.with_child(side, subtree));
+        }
+        subtree
+    }
+}
+
+/**
+ * Immutable unbalanced binary search tree with structural sharing.
+ * Cloning of the tree is cheap, because it shares all nodes.
+ */
+pub struct PersistentTree<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+impl<K, V> Default for PersistentTree<K, V> {
+    fn default() -> Self {
+        Self { root: None, len: 0 }
+    }
+}
+
+impl<K, V> Clone for PersistentTree<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K, V> Drop for PersistentTree<K, V> {
+    /**
+     * Release nodes in a loop, because the default drop of nested links
+     * recurses once per level. Only nodes, which are not shared with
+     * other versions of the tree, are taken apart, shared ones are just
+     * released.
+     */
+    fn drop(&mut self) {
+        let mut stack: Vec<Rc<Node<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            if let Ok(mut node) = Rc::try_unwrap(node) {
+                stack.extend(node.left.take());
+                stack.extend(node.right.take());
+            }
+        }
+    }
+}
+
+impl<K, V> PersistentTree<K, V> {
+    /**
+     * Iterate over references to keys and values in ascending key order.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        InOrder::new(self.root.as_deref())
+    }
+
+    /**
+     * Number of key-value pairs in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<K, V> PersistentTree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Check is the key present in the tree.
+     */
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /**
+     * Get reference to the value stored under the key.
+     */
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        binary_node::find(self.root.as_deref(), key).map(BinaryNode::value)
+    }
+}
+
+impl<K, V> PersistentTree<K, V>
+where
+    K: Ord + Clone,
+    V: Clone,
+{
+    /**
+     * New version of the tree with the key-value pair inserted. When
+     * the key is already present, its value is replaced in the new version.
+     */
+    pub fn insert(&self, key: K, value: V) -> PersistentTree<K, V> {
+        let mut path = Vec::new();
+        let mut subtree = self.root.as_deref();
+
+        let (node, is_new) = loop {
+            let Some(node) = subtree else {
+                break (Node::new(key, value, None, None), true);
+            };
+            let side = key.cmp(&node.key);
+            subtree = match side {
+                Ordering::Equal => {
+                    let (left, right) = (node.left.clone(), node.right.clone());
+                    break (Node::new(key, value, left, right), false);
+                }
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+            };
+            path.push((node, side));
+        };
+
+        PersistentTree {
+            root: Node::rebuild_path(path, Some(Rc::new(node))),
+            len: self.len + usize::from(is_new),
+        }
+    }
+
+    /**
+     * New version of the tree without the key. When the key is not
+     * present, the new version shares the whole tree with this one.
+     */
+    pub fn remove<Q>(&self, key: &Q) -> PersistentTree<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut path = Vec::new();
+        let mut subtree = self.root.as_deref();
+
+        let removed_node = loop {
+            let Some(node) = subtree else {
+                return self.clone();
+            };
+            let side = key.cmp(node.key.borrow());
+            subtree = match side {
+                Ordering::Equal => break node,
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+            };
+            path.push((node, side));
+        };
+
+        let replacement = match (&removed_node.left, &removed_node.right) {
+            (None, child) | (child, None) => child.clone(),
+
+            (Some(left), Some(right)) => {
+                // The leftmost node of the right subtree takes the place,
+                // and the path to it is copied without it.
+                let mut spine = Vec::new();
+                let mut min_node = &**right;
+                while let Some(next) = min_node.left.as_deref() {
+                    spine.push((min_node, Ordering::Less));
+                    min_node = next;
+                }
+                let right = Node::rebuild_path(spine, min_node.right.clone());
+                let (key, value) = (min_node.key.clone(), min_node.value.clone());
+                Some(Rc::new(Node::new(key, value, Some(left.clone()), right)))
+            }
+        };
+
+        PersistentTree {
+            root: Node::rebuild_path(path, replacement),
+            len: self.len - 1,
+        }
+    }
+}
+
+impl<K, V> fmt::Debug for PersistentTree<K, V>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PersistentTree;
+
+    fn demo_tree() -> PersistentTree<i32, i32> {
+        [5, 3, 65, 123, 6, 11, 3, 1, 5, 42]
+            .into_iter()
+            .fold(PersistentTree::new(), |tree, i| tree.insert(i, i))
+    }
+
+    #[test]
+    fn insert_keeps_old_version() {
+        let t1 = demo_tree();
+        let t2 = t1.insert(99, 99);
+
+        assert_eq!(t1.get(&99), None);
+        assert_eq!(t2.get(&99), Some(&99));
+        assert_eq!((t1.len(), t2.len()), (8, 9));
+
+        let t3 = t2.insert(5, 50);
+        assert_eq!(t2.get(&5), Some(&5));
+        assert_eq!(t3.get(&5), Some(&50));
+        assert_eq!(t3.len(), 9);
+        assert!(t3
+            .iter()
+            .map(|(k, _)| *k)
+            .eq([1, 3, 5, 6, 11, 42, 65, 99, 123]));
+    }
+
+    #[test]
+    fn remove_keeps_old_version() {
+        let t1 = demo_tree();
+
+        // The root has two children, so its successor takes its place.
+        let t2 = t1.remove(&5);
+        assert_eq!(t1.get(&5), Some(&5));
+        assert_eq!(t2.get(&5), None);
+        assert!(t2.iter().map(|(k, _)| *k).eq([1, 3, 6, 11, 42, 65, 123]));
+
+        let t3 = t2.remove(&3).remove(&123).remove(&7);
+        assert!(t3.iter().map(|(k, _)| *k).eq([1, 6, 11, 42, 65]));
+        assert_eq!(t3.len(), 5);
+        assert_eq!(t2.len(), 7);
+        assert_eq!(t1.len(), 8);
+    }
+
+    #[test]
+    fn unchanged_subtrees_are_shared() {
+        let t1 = demo_tree();
+        let t2 = t1.insert(0, 0);
+
+        // Only the left path is copied, the right subtree of the root is shared.
+        let (Some(root1), Some(root2)) = (&t1.root, &t2.root) else {
+            panic!("trees are not empty");
+        };
+        assert!(!alloc::rc::Rc::ptr_eq(root1, root2));
+        assert!(alloc::rc::Rc::ptr_eq(
+            root1.right.as_ref().unwrap(),
+            root2.right.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn drop_deep_chain_without_stack_overflow() {
+        let thread = std::thread::Builder::new().stack_size(64 * 1024);
+        let handle = thread
+            .spawn(|| {
+                // Each insert copies the whole chain and drops the old one.
+                let mut tree = PersistentTree::new();
+                for i in 0..5_000 {
+                    tree = tree.insert(i, i);
+                }
+                let shared = tree.clone();
+                tree = tree.insert(5_000, 5_000);
+                drop(shared);
+
+                assert_eq!(tree.len(), 5_001);
+                assert_eq!(tree.get(&0), Some(&0));
+            })
+            .unwrap();
+        handle.join().unwrap();
+    }
+}
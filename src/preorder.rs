@@ -0,0 +1,190 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Compact text encoding of the tree in pre-order, which preserves
+//! the shape of the tree.
+//!
+//! Tokens are separated by spaces. A node is encoded as `key:value`
+//! and followed by encodings of its left and right subtrees, an empty
+//! subtree is encoded as `#`. Keys must not contain `:`, keys and values
+//! must not contain whitespace.
+
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Display, Write};
+use core::str::FromStr;
+
+use crate::{Node, ParseError, Tree};
+
+const NULL_MARKER: &str = "#";
+
+impl<K, V> Tree<K, V>
+where
+    K: Display,
+    V: Display,
+{
+    /**
+     * Encode the tree in pre-order with explicit markers of empty subtrees.
+     */
+    pub fn serialize_preorder(&self) -> String {
+        let mut encoded = String::new();
+        let mut stack = alloc::vec![self.root.as_deref()];
+
+        while let Some(subtree) = stack.pop() {
+            if !encoded.is_empty() {
+                encoded.push(' ');
+            }
+            match subtree {
+                None => encoded.push_str(NULL_MARKER),
+                Some(node) => {
+                    // Writing into a string cannot fail.
+                    let _ = write!(encoded, "{}:{}", node.key, node.value);
+                    stack.push(node.right.as_deref());
+                    stack.push(node.left.as_deref());
+                }
+            }
+        }
+        encoded
+    }
+}
+
+impl<K, V> Tree<K, V>
+where
+    K: Ord + FromStr,
+    V: FromStr,
+{
+    /**
+     * Rebuild the tree of exactly the same shape from its pre-order encoding.
+     */
+    pub fn deserialize_preorder(s: &str) -> Result<Tree<K, V>, ParseError> {
+        let mut tokens = s.split_whitespace();
+        let mut tree = Tree::new();
+
+        // Nodes in the order of creation, and nodes, which still wait for
+        // their children, with a flag, whether the left child is parsed.
+        let mut nodes: Vec<*mut Node<K, V>> = Vec::new();
+        let mut pending: Vec<(*mut Node<K, V>, bool)> = Vec::new();
+
+        let token = tokens.next().ok_or(ParseError::UnexpectedEnd)?;
+        if let Some((key, value)) = parse_entry(token)? {
+            let root = tree.root.insert(Box::new(Node::new_root(key, value)));
+            let root_ptr: *mut Node<K, V> = &mut **root;
+            nodes.push(root_ptr);
+            pending.push((root_ptr, false));
+        }
+
+        while let Some((parent_ptr, left_is_parsed)) = pending.pop() {
+            let token = tokens.next().ok_or(ParseError::UnexpectedEnd)?;
+            if !left_is_parsed {
+                pending.push((parent_ptr, true));
+            }
+
+            if let Some((key, value)) = parse_entry(token)? {
+                // The parent is owned by the tree, which is not borrowed.
+                let parent = unsafe { &mut *parent_ptr };
+                let slot = match left_is_parsed {
+                    false => &mut parent.left,
+                    true => &mut parent.right,
+                };
+                let child = slot.insert(Box::new(Node::new_leaf(key, value, parent_ptr)));
+                let child_ptr: *mut Node<K, V> = &mut **child;
+                nodes.push(child_ptr);
+                pending.push((child_ptr, false));
+            }
+        }
+
+        if let Some(token) = tokens.next() {
+            return Err(ParseError::TrailingInput(token.to_owned()));
+        }
+
+        // Children are created after their parents, so sizes are fixed
+        // bottom-up, when nodes are visited in reverse order of creation.
+        for &node_ptr in nodes.iter().rev() {
+            let node = unsafe { &mut *node_ptr };
+            node.size = 1 + Node::subtree_size(&node.left) + Node::subtree_size(&node.right);
+        }
+        tree.len = nodes.len();
+
+        if !tree.is_valid_bst() {
+            return Err(ParseError::NotOrdered);
+        }
+        Ok(tree)
+    }
+}
+
+/**
+ * Parse a token into an entry, or into None for the null marker.
+ */
+fn parse_entry<K: FromStr, V: FromStr>(token: &str) -> Result<Option<(K, V)>, ParseError> {
+    if token == NULL_MARKER {
+        return Ok(None);
+    }
+
+    let (key, value) = token
+        .split_once(':')
+        .ok_or_else(|| ParseError::InvalidToken(token.to_owned()))?;
+    let key = key
+        .parse()
+        .map_err(|_| ParseError::InvalidKey(key.to_owned()))?;
+    let value = value
+        .parse()
+        .map_err(|_| ParseError::InvalidValue(value.to_owned()))?;
+    Ok(Some((key, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ParseError, Tree};
+
+    fn demo_tree() -> Tree<i32, i32> {
+        let mut tree = Tree::new();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i);
+        }
+        tree
+    }
+
+    #[test]
+    fn preorder_round_trip_keeps_shape() {
+        let tree = demo_tree();
+
+        let encoded = tree.serialize_preorder();
+        assert_eq!(
+            encoded,
+            "5:5 3:3 1:1 # # # 65:65 6:6 # 11:11 # 42:42 # # 123:123 # #"
+        );
+
+        let decoded: Tree<i32, i32> = Tree::deserialize_preorder(&encoded).unwrap();
+        assert!(decoded.structurally_equal(&tree));
+        assert_eq!(decoded, tree);
+        assert_eq!(decoded.len(), 8);
+        assert_eq!(decoded.successor(&42), Some(&65));
+        assert_eq!(decoded.select(3), Some((&6, &6)));
+    }
+
+    #[test]
+    fn preorder_of_empty_tree() {
+        let tree: Tree<i32, i32> = Tree::new();
+
+        assert_eq!(tree.serialize_preorder(), "#");
+        let decoded: Tree<i32, i32> = Tree::deserialize_preorder("#").unwrap();
+        assert!(decoded.is_empty());
+    }
+
+    #[test]
+    fn deserialize_preorder_rejects_malformed_input() {
+        let parse = Tree::<i32, i32>::deserialize_preorder;
+
+        assert_eq!(parse(""), Err(ParseError::UnexpectedEnd));
+        assert_eq!(parse("5:5 #"), Err(ParseError::UnexpectedEnd));
+        assert_eq!(
+            parse("5:5 # # #"),
+            Err(ParseError::TrailingInput("#".into()))
+        );
+        assert_eq!(parse("5 # #"), Err(ParseError::InvalidToken("5".into())));
+        assert_eq!(parse("x:5 # #"), Err(ParseError::InvalidKey("x".into())));
+        assert_eq!(parse("5:y # #"), Err(ParseError::InvalidValue("y".into())));
+        assert_eq!(parse("5:5 7:7 # # #"), Err(ParseError::NotOrdered));
+    }
+}
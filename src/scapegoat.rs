@@ -0,0 +1,391 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Scapegoat tree. Nodes store nothing but keys, values and children.
+//! When an insert goes deeper, than the height allowed for the size of
+//! the tree, the first ancestor with too heavy child, i.e. scapegoat, is
+//! rebuilt into a perfectly balanced subtree, and after many removes the
+//! whole tree is rebuilt, so the height stays O(log n) with amortized
+//! O(log n) updates.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+
+use crate::binary_node::{self, BinaryNode, InOrder, OwnedNode};
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+/**
+ * Default balance factor: a child of a subtree may hold up to 70% of its
+ * nodes.
+ */
+const DEFAULT_ALPHA: f64 = 0.7;
+
+#[derive(Debug)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl<K, V> Node<K, V> {
+    /**
+     * Number of nodes in the subtree, counted by a walk over it.
+     */
+    fn size(link: &Link<K, V>) -> usize {
+        let mut size = 0;
+        let mut stack: Vec<&Node<K, V>> = link.as_deref().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            size += 1;
+            stack.extend(node.left.as_deref());
+            stack.extend(node.right.as_deref());
+        }
+        size
+    }
+}
+
+impl<K, V> BinaryNode for Node<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn key(&self) -> &K {
+        &self.key
+    }
+
+    fn value(&self) -> &V {
+        &self.value
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+impl<K, V> OwnedNode for Node<K, V> {
+    fn value_mut(&mut self) -> &mut V {
+        &mut self.value
+    }
+
+    fn left_link(&mut self) -> &mut Link<K, V> {
+        &mut self.left
+    }
+
+    fn right_link(&mut self) -> &mut Link<K, V> {
+        &mut self.right
+    }
+}
+
+/**
+ * Result of an insert into a subtree.
+ */
+enum Inserted<V> {
+    // The key was present, its previous value is returned.
+    Replaced(V),
+
+    // The new node is not too deep, or its scapegoat is already rebuilt.
+    Done,
+
+    // The new node is too deep, and the scapegoat is not found yet.
+    // Holds the size of the subtree, which contains the new node.
+    TooDeep(usize),
+}
+
+/**
+ * Balanced binary search tree with the same API as `Tree` and without
+ * balancing data in nodes, only the tree keeps its size.
+ */
+#[derive(Debug)]
+pub struct ScapegoatTree<K, V> {
+    root: Link<K, V>,
+    len: usize,
+
+    // Largest length since the last rebuild of the whole tree.
+    max_len: usize,
+
+    // A subtree is balanced, when none of its children holds more than
+    // alpha of its nodes.
+    alpha: f64,
+}
+
+impl<K, V> Default for ScapegoatTree<K, V> {
+    fn default() -> Self {
+        Self::with_alpha(DEFAULT_ALPHA)
+    }
+}
+
+impl<K, V> ScapegoatTree<K, V> {
+    /**
+     * Create an empty tree with the balance factor, which must be in range
+     * `0.5 < alpha < 1`. Lower alpha keeps the tree lower, but rebuilds
+     * subtrees more often.
+     */
+    pub fn with_alpha(alpha: f64) -> Self {
+        assert!(
+            alpha > 0.5 && alpha < 1.0,
+            "alpha must be between 0.5 and 1"
+        );
+        Self {
+            root: None,
+            len: 0,
+            max_len: 0,
+            alpha,
+        }
+    }
+
+    /**
+     * Iterate over references to keys and values in ascending key order.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        InOrder::new(self.root.as_deref())
+    }
+
+    /**
+     * Number of key-value pairs in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Number of nodes on the longest path from the root to a leaf.
+     */
+    pub fn height(&self) -> usize {
+        binary_node::height(self.root.as_deref())
+    }
+
+    /**
+     * Largest allowed depth of a node, counting the root as 0,
+     * i.e. `log(len)` with base `1 / alpha`.
+     */
+    fn depth_limit(&self) -> usize {
+        let mut depth = 0;
+        let mut weight = 1.0 / self.alpha;
+        while weight <= self.len as f64 {
+            weight /= self.alpha;
+            depth += 1;
+        }
+        depth
+    }
+}
+
+impl<K, V> ScapegoatTree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Insert key-value pair into tree. When the key is already present,
+     * its value is replaced and the previous value is returned.
+     */
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.len += 1;
+        let depth_limit = self.depth_limit();
+        match Self::insert_at(&mut self.root, key, value, Some(depth_limit), self.alpha) {
+            Inserted::Replaced(previous) => {
+                self.len -= 1;
+                Some(previous)
+            }
+            Inserted::Done | Inserted::TooDeep(_) => {
+                self.max_len = self.max_len.max(self.len);
+                None
+            }
+        }
+    }
+
+    /**
+     * Insert key-value pair into tree, only when the key is not present.
+     * Returns false and keeps the existing value otherwise.
+     */
+    pub fn try_insert(&mut self, key: K, value: V) -> bool {
+        if self.contains(&key) {
+            return false;
+        }
+        self.insert(key, value);
+        true
+    }
+
+    /**
+     * Insert the key into the subtree, which has `levels_left` levels
+     * allowed below its root, or none, when the root is too deep already.
+     * Sizes of subtrees are counted only on the way back from a too deep
+     * node, until the scapegoat is found.
+     */
+    fn insert_at(
+        slot: &mut Link<K, V>,
+        key: K,
+        value: V,
+        levels_left: Option<usize>,
+        alpha: f64,
+    ) -> Inserted<V> {
+        let Some(current_node) = slot else {
+            *slot = Some(Box::new(Node {
+                key,
+                value,
+                left: None,
+                right: None,
+            }));
+            return match levels_left {
+                None => Inserted::TooDeep(1),
+                Some(_) => Inserted::Done,
+            };
+        };
+
+        let limit = levels_left.and_then(|levels| levels.checked_sub(1));
+        let (inserted, sibling) = match key.cmp(&current_node.key) {
+            Ordering::Equal => {
+                let previous = core::mem::replace(&mut current_node.value, value);
+                return Inserted::Replaced(previous);
+            }
+            Ordering::Less => (
+                Self::insert_at(&mut current_node.left, key, value, limit, alpha),
+                &current_node.right,
+            ),
+            Ordering::Greater => (
+                Self::insert_at(&mut current_node.right, key, value, limit, alpha),
+                &current_node.left,
+            ),
+        };
+
+        let Inserted::TooDeep(child_size) = inserted else {
+            return inserted;
+        };
+        let size = child_size + Node::size(sibling) + 1;
+        if child_size as f64 > alpha * size as f64 {
+            binary_node::rebuild(slot);
+            return Inserted::Done;
+        }
+        Inserted::TooDeep(size)
+    }
+
+    /**
+     * Check is the key present in the tree.
+     */
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /**
+     * Get reference to the value stored under the key.
+     */
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        binary_node::find(self.root.as_deref(), key).map(BinaryNode::value)
+    }
+
+    /**
+     * Get mutable reference to the value stored under the key.
+     */
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        binary_node::find_mut(self.root.as_deref_mut(), key).map(OwnedNode::value_mut)
+    }
+
+    /**
+     * Remove node with the key from tree and return its value. The whole
+     * tree is rebuilt, when it shrinks below alpha of its largest length.
+     */
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut slot = &mut self.root;
+        loop {
+            match key.cmp(slot.as_ref()?.key.borrow()) {
+                Ordering::Equal => break,
+                Ordering::Less => slot = &mut slot.as_mut()?.left,
+                Ordering::Greater => slot = &mut slot.as_mut()?.right,
+            }
+        }
+
+        let mut removed_node = slot.take()?;
+        *slot = match (removed_node.left.take(), removed_node.right.take()) {
+            (None, child) | (child, None) => child,
+
+            (Some(left), Some(right)) => {
+                let mut right = Some(right);
+                let mut successor = Self::remove_min(&mut right)?;
+                successor.left = Some(left);
+                successor.right = right;
+                Some(successor)
+            }
+        };
+
+        self.len -= 1;
+        if (self.len as f64) < self.alpha * self.max_len as f64 {
+            binary_node::rebuild(&mut self.root);
+            self.max_len = self.len;
+        }
+        Some(removed_node.value)
+    }
+
+    /**
+     * Detach the leftmost node of the subtree in the slot.
+     */
+    fn remove_min(mut slot: &mut Link<K, V>) -> Option<Box<Node<K, V>>> {
+        while slot.as_ref()?.left.is_some() {
+            slot = &mut slot.as_mut()?.left;
+        }
+        let mut min_node = slot.take()?;
+        *slot = min_node.right.take();
+        Some(min_node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lower_alpha_keeps_tree_lower() {
+        let mut tree = ScapegoatTree::new();
+        let mut low = ScapegoatTree::with_alpha(0.55);
+        for i in 0..10_000 {
+            tree.insert(i, i);
+            low.insert(i, i);
+        }
+        assert!(low.height() < tree.height(), "height is {}", low.height());
+    }
+
+    #[test]
+    fn nodes_have_no_balancing_data() {
+        type Bare = (u64, u64, Link<u64, u64>, Link<u64, u64>);
+        assert_eq!(
+            core::mem::size_of::<Node<u64, u64>>(),
+            core::mem::size_of::<Bare>()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "alpha")]
+    fn alpha_must_be_above_half() {
+        ScapegoatTree::<i32, i32>::with_alpha(0.5);
+    }
+}
@@ -0,0 +1,80 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Serialization of the tree as an ordered sequence of key-value pairs.
+//! The format does not depend on the shape of the tree.
+
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::Tree;
+
+impl<K, V> Serialize for Tree<K, V>
+where
+    K: Serialize,
+    V: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, K, V> Deserialize<'de> for Tree<K, V>
+where
+    K: Deserialize<'de> + Ord,
+    V: Deserialize<'de>,
+{
+    /**
+     * Rebuild the tree by inserting pairs in the order of the sequence.
+     */
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(TreeVisitor(PhantomData))
+    }
+}
+
+struct TreeVisitor<K, V>(PhantomData<(K, V)>);
+
+impl<'de, K, V> Visitor<'de> for TreeVisitor<K, V>
+where
+    K: Deserialize<'de> + Ord,
+    V: Deserialize<'de>,
+{
+    type Value = Tree<K, V>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of key-value pairs")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut tree = Tree::new();
+        while let Some((key, value)) = seq.next_element()? {
+            tree.insert(key, value);
+        }
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Tree;
+
+    #[test]
+    fn json_round_trip() {
+        let tree: Tree<i32, String> = [5, 3, 65, 123, 6, 11, 3, 1, 5, 42]
+            .into_iter()
+            .map(|i| (i, i.to_string()))
+            .collect();
+
+        let json = serde_json::to_string(&tree).unwrap();
+        assert_eq!(
+            json,
+            r#"[[1,"1"],[3,"3"],[5,"5"],[6,"6"],[11,"11"],[42,"42"],[65,"65"],[123,"123"]]"#
+        );
+
+        let restored: Tree<i32, String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, tree);
+        assert_eq!(restored.successor(&6), Some(&11));
+    }
+}
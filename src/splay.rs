@@ -0,0 +1,379 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Splay tree. Every access moves the accessed node to the root with
+//! rotations, so recently used keys are found fast, and updates take
+//! amortized O(log n) time, while the tree itself may become deep.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+
+use crate::binary_node::{self, BinaryNode, InOrder};
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+#[derive(Debug)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl<K, V> Node<K, V> {
+    fn new(key: K, value: V) -> Self {
+        Self {
+            key,
+            value,
+            left: None,
+            right: None,
+        }
+    }
+}
+
+impl<K, V> BinaryNode for Node<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn key(&self) -> &K {
+        &self.key
+    }
+
+    fn value(&self) -> &V {
+        &self.value
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+/**
+ * Self-adjusting binary search tree with the same API as `Tree`.
+ * Lookups restructure the tree, so `get_splay` needs a mutable reference.
+ */
+#[derive(Debug)]
+pub struct SplayTree<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+impl<K, V> Default for SplayTree<K, V> {
+    fn default() -> Self {
+        Self { root: None, len: 0 }
+    }
+}
+
+impl<K, V> Drop for SplayTree<K, V> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<K, V> SplayTree<K, V> {
+    /**
+     * Iterate over references to keys and values in ascending key order.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        InOrder::new(self.root.as_deref())
+    }
+
+    /**
+     * Number of key-value pairs in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Number of nodes on the longest path from the root to a leaf.
+     */
+    pub fn height(&self) -> usize {
+        binary_node::height(self.root.as_deref())
+    }
+
+    /**
+     * Remove all entries from the tree. Nodes are unlinked in a loop,
+     * because the tree may be a deep chain after sorted inserts.
+     */
+    pub fn clear(&mut self) {
+        let mut stack: Vec<Box<Node<K, V>>> = self.root.take().into_iter().collect();
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+        self.len = 0;
+    }
+}
+
+impl<K, V> SplayTree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Insert key-value pair into tree and move it to the root. When the key
+     * is already present, its value is replaced and the previous value
+     * is returned.
+     */
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let Some(mut root) = Self::splay(self.root.take(), &key) else {
+            self.root = Some(Box::new(Node::new(key, value)));
+            self.len = 1;
+            return None;
+        };
+
+        // The root is the closest key, so it splits the tree by the new key.
+        let mut node = Box::new(Node::new(key, value));
+        match node.key.cmp(&root.key) {
+            Ordering::Equal => {
+                let previous = core::mem::replace(&mut root.value, node.value);
+                self.root = Some(root);
+                return Some(previous);
+            }
+            Ordering::Less => {
+                node.left = root.left.take();
+                node.right = Some(root);
+            }
+            Ordering::Greater => {
+                node.right = root.right.take();
+                node.left = Some(root);
+            }
+        }
+        self.root = Some(node);
+        self.len += 1;
+        None
+    }
+
+    /**
+     * Insert key-value pair into tree, only when the key is not present.
+     * Returns false and keeps the existing value otherwise.
+     */
+    pub fn try_insert(&mut self, key: K, value: V) -> bool {
+        if self.get_splay(&key).is_some() {
+            return false;
+        }
+        self.insert(key, value);
+        true
+    }
+
+    /**
+     * Check is the key present in the tree. The tree is not restructured.
+     */
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        binary_node::find(self.root.as_deref(), key).is_some()
+    }
+
+    /**
+     * Get reference to the value stored under the key and move the node
+     * to the root. When the key is missing, the last visited node is moved.
+     */
+    pub fn get_splay<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_mut(key).map(|value| &*value)
+    }
+
+    /**
+     * Get mutable reference to the value stored under the key and move
+     * the node to the root, like `get_splay`.
+     */
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.root = Self::splay(self.root.take(), key);
+        self.root
+            .as_deref_mut()
+            .filter(|root| root.key.borrow() == key)
+            .map(|root| &mut root.value)
+    }
+
+    /**
+     * Remove node with the key from tree and return its value.
+     */
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut root = Self::splay(self.root.take(), key)?;
+        if root.key.borrow() != key {
+            self.root = Some(root);
+            return None;
+        }
+
+        // All keys of the left subtree are less than the key, so splaying
+        // of it by the key brings its maximum up, which has no right child.
+        self.root = match (root.left.take(), root.right.take()) {
+            (None, right) => right,
+            (left, right) => Self::splay(left, key).map(|mut max_node| {
+                max_node.right = right;
+                max_node
+            }),
+        };
+        self.len -= 1;
+        Some(root.value)
+    }
+
+    /**
+     * Top-down splay: move the node with the key, or the last node
+     * on the search path to it, to the root of the subtree. Nodes
+     * passed on the way are collected into the left tree, which gets
+     * keys less than the key, and the right tree, which gets greater
+     * keys, and then both trees become children of the new root.
+     */
+    fn splay<Q>(subtree: Link<K, V>, key: &Q) -> Link<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut node = subtree?;
+        let mut lesser: Vec<Box<Node<K, V>>> = Vec::new();
+        let mut greater: Vec<Box<Node<K, V>>> = Vec::new();
+
+        loop {
+            match key.cmp(node.key.borrow()) {
+                Ordering::Equal => break,
+
+                Ordering::Less => {
+                    let Some(mut left) = node.left.take() else {
+                        break;
+                    };
+                    if key < left.key.borrow() {
+                        // Zig-zig: rotate right before linking.
+                        node.left = left.right.take();
+                        left.right = Some(node);
+                        node = left;
+                        let Some(next) = node.left.take() else {
+                            break;
+                        };
+                        greater.push(node);
+                        node = next;
+                    } else {
+                        greater.push(node);
+                        node = left;
+                    }
+                }
+
+                Ordering::Greater => {
+                    let Some(mut right) = node.right.take() else {
+                        break;
+                    };
+                    if key > right.key.borrow() {
+                        // Zag-zag: rotate left before linking.
+                        node.right = right.left.take();
+                        right.left = Some(node);
+                        node = right;
+                        let Some(next) = node.right.take() else {
+                            break;
+                        };
+                        lesser.push(node);
+                        node = next;
+                    } else {
+                        lesser.push(node);
+                        node = right;
+                    }
+                }
+            }
+        }
+
+        // Each node of the left tree hangs as right child of the previous
+        // one, and each node of the right tree as left child.
+        let mut left = node.left.take();
+        for mut lesser_node in lesser.into_iter().rev() {
+            lesser_node.right = left;
+            left = Some(lesser_node);
+        }
+        let mut right = node.right.take();
+        for mut greater_node in greater.into_iter().rev() {
+            greater_node.left = right;
+            right = Some(greater_node);
+        }
+        node.left = left;
+        node.right = right;
+        Some(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root_key<K: Copy, V>(tree: &SplayTree<K, V>) -> Option<K> {
+        tree.root.as_ref().map(|node| node.key)
+    }
+
+    #[test]
+    fn accessed_key_moves_to_root() {
+        let mut tree = SplayTree::new();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i);
+        }
+        assert_eq!(root_key(&tree), Some(42));
+
+        for _ in 0..3 {
+            assert_eq!(tree.get_splay(&6), Some(&6));
+            assert_eq!(root_key(&tree), Some(6));
+        }
+        assert_eq!(tree.get_splay(&7), None);
+        assert!(matches!(root_key(&tree), Some(6 | 11)));
+        assert!(tree
+            .iter()
+            .map(|(k, _)| *k)
+            .eq([1, 3, 5, 6, 11, 42, 65, 123]));
+    }
+
+    #[test]
+    fn sorted_access_of_deep_chain() {
+        let mut tree = SplayTree::new();
+        for i in 0..100_000 {
+            assert_eq!(tree.insert(i, i), None);
+        }
+        assert_eq!(tree.height(), 100_000);
+
+        // Splaying of the deepest node roughly halves the depth.
+        assert_eq!(tree.get_splay(&0), Some(&0));
+        assert!(tree.height() < 60_000, "height is {}", tree.height());
+        assert!(tree.iter().map(|(k, _)| *k).eq(0..100_000));
+        assert_eq!(tree.len(), 100_000);
+    }
+
+    #[test]
+    fn remove_joins_subtrees() {
+        let mut tree = SplayTree::new();
+        for i in 0..1_000 {
+            tree.insert((i * 7) % 1_000, i);
+        }
+
+        for key in (0..1_000).filter(|key| key % 3 != 0) {
+            assert!(tree.remove(&key).is_some());
+        }
+        assert_eq!(tree.remove(&1), None);
+        assert_eq!(tree.len(), 334);
+        assert!(tree.iter().map(|(k, _)| *k).eq((0..1_000).step_by(3)));
+    }
+}
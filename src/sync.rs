@@ -0,0 +1,167 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Tree, which can be shared between threads. Needs the `std` feature.
+
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::Tree;
+
+/**
+ * Tree behind a read-write lock, so it can be shared between threads
+ * through `Arc` without external locking. Readers work in parallel,
+ * writers get exclusive access. Values are cloned out of the lock.
+ */
+#[derive(Debug)]
+pub struct SyncTree<K, V> {
+    tree: RwLock<Tree<K, V>>,
+}
+
+impl<K, V> Default for SyncTree<K, V> {
+    fn default() -> Self {
+        Self::from(Tree::default())
+    }
+}
+
+impl<K, V> SyncTree<K, V> {
+    /**
+     * Number of key-value pairs in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /**
+     * Unwrap the tree, when it is not shared anymore.
+     */
+    pub fn into_inner(self) -> Tree<K, V> {
+        self.tree
+            .into_inner()
+            .expect("lock of the tree is poisoned")
+    }
+
+    /**
+     * Lock the tree for reading. A panic in a writer may leave the tree
+     * inconsistent, so a poisoned lock is reported with a panic too.
+     */
+    fn read(&self) -> RwLockReadGuard<'_, Tree<K, V>> {
+        self.tree.read().expect("lock of the tree is poisoned")
+    }
+
+    /**
+     * Lock the tree for writing, see `read` for poisoning.
+     */
+    fn write(&self) -> RwLockWriteGuard<'_, Tree<K, V>> {
+        self.tree.write().expect("lock of the tree is poisoned")
+    }
+}
+
+impl<K, V> SyncTree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Insert key-value pair under the write lock. When the key is already
+     * present, its value is replaced and the previous value is returned.
+     */
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.write().insert(key, value)
+    }
+
+    /**
+     * Remove the key under the write lock and return its value.
+     */
+    pub fn remove(&self, key: &K) -> Option<V> {
+        self.write().remove(key)
+    }
+
+    /**
+     * Check is the key present in the tree.
+     */
+    pub fn contains(&self, key: &K) -> bool {
+        self.read().contains(key)
+    }
+
+    /**
+     * Copy of the value stored under the key, cloned under the read lock.
+     */
+    pub fn get(&self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.read().get(key).cloned()
+    }
+}
+
+impl<K, V> From<Tree<K, V>> for SyncTree<K, V> {
+    fn from(tree: Tree<K, V>) -> Self {
+        Self {
+            tree: RwLock::new(tree),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::SyncTree;
+
+    #[test]
+    fn concurrent_reads_and_writes() {
+        let tree = Arc::new(SyncTree::new());
+        for i in 0..100 {
+            tree.insert(i, i);
+        }
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let tree = Arc::clone(&tree);
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        for i in 0..100 {
+                            // Writers only add keys from 100 up, so these
+                            // entries are stable.
+                            assert_eq!(tree.get(&i), Some(i));
+                        }
+                    }
+                })
+            })
+            .collect();
+        let writers: Vec<_> = (0..2)
+            .map(|writer| {
+                let tree = Arc::clone(&tree);
+                thread::spawn(move || {
+                    for i in 0..50 {
+                        tree.insert(100 + writer * 50 + i, writer);
+                    }
+                })
+            })
+            .collect();
+
+        for handle in readers.into_iter().chain(writers) {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(tree.len(), 200);
+        assert_eq!(tree.get(&120), Some(0));
+        assert_eq!(tree.get(&170), Some(1));
+        assert_eq!(tree.remove(&0), Some(0));
+        assert!(!tree.contains(&0));
+
+        let tree = Arc::into_inner(tree).unwrap().into_inner();
+        assert_eq!(tree.len(), 199);
+        assert!(tree.is_valid_bst());
+    }
+}
@@ -0,0 +1,295 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Right-threaded binary search tree. The right link of a node without
+//! right child points to its in-order successor instead, so iteration
+//! needs neither a stack nor parent pointers. Nodes are stored in
+//! a vector and linked by indices, like in `ArenaTree`.
+
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+
+use crate::Tree;
+
+/**
+ * Right link of a node: either a real child, or a thread to the in-order
+ * successor, which is None for the last node.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Right {
+    Child(usize),
+    Thread(Option<usize>),
+}
+
+#[derive(Debug, Clone)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+
+    left: Option<usize>,
+    right: Right,
+}
+
+/**
+ * Unbalanced binary search tree with threads to in-order successors.
+ */
+#[derive(Debug, Clone)]
+pub struct ThreadedTree<K, V> {
+    nodes: Vec<Node<K, V>>,
+    root: Option<usize>,
+}
+
+impl<K, V> Default for ThreadedTree<K, V> {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            root: None,
+        }
+    }
+}
+
+impl<K, V> ThreadedTree<K, V> {
+    /**
+     * Iterate over references to keys and values in ascending key order.
+     * The iterator holds only the index of the next node.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        Threads {
+            nodes: &self.nodes,
+            next: self.root.map(|index| self.leftmost(index)),
+        }
+    }
+
+    /**
+     * Number of key-value pairs in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /**
+     * Remove all entries from the tree.
+     */
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = None;
+    }
+
+    fn leftmost(&self, mut index: usize) -> usize {
+        while let Some(left) = self.nodes[index].left {
+            index = left;
+        }
+        index
+    }
+}
+
+impl<K, V> ThreadedTree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Insert key-value pair into tree. When the key is already present,
+     * its value is replaced and the previous value is returned.
+     * A new left child threads to its parent, a new right child takes
+     * over the thread of its parent.
+     */
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let new_index = self.nodes.len();
+        let Some(mut index) = self.root else {
+            self.root = Some(new_index);
+            self.nodes.push(Node::new(key, value, None));
+            return None;
+        };
+
+        let thread = loop {
+            let node = &mut self.nodes[index];
+            match key.cmp(&node.key) {
+                Ordering::Equal => return Some(core::mem::replace(&mut node.value, value)),
+
+                Ordering::Less => match node.left {
+                    Some(left) => index = left,
+                    None => {
+                        node.left = Some(new_index);
+                        break Some(index);
+                    }
+                },
+
+                Ordering::Greater => match node.right {
+                    Right::Child(right) => index = right,
+                    Right::Thread(successor) => {
+                        node.right = Right::Child(new_index);
+                        break successor;
+                    }
+                },
+            }
+        };
+
+        self.nodes.push(Node::new(key, value, thread));
+        None
+    }
+
+    /**
+     * Check is the key present in the tree.
+     */
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_index(key).is_some()
+    }
+
+    /**
+     * Get reference to the value stored under the key.
+     */
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_index(key).map(|index| &self.nodes[index].value)
+    }
+
+    /**
+     * Get mutable reference to the value stored under the key.
+     */
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let index = self.find_index(key)?;
+        Some(&mut self.nodes[index].value)
+    }
+
+    fn find_index<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut subtree = self.root;
+        while let Some(index) = subtree {
+            let node = &self.nodes[index];
+            subtree = match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return Some(index),
+                Ordering::Less => node.left,
+                Ordering::Greater => match node.right {
+                    Right::Child(right) => Some(right),
+                    Right::Thread(_) => None,
+                },
+            };
+        }
+        None
+    }
+}
+
+impl<K, V> Node<K, V> {
+    fn new(key: K, value: V, successor: Option<usize>) -> Self {
+        Self {
+            key,
+            value,
+            left: None,
+            right: Right::Thread(successor),
+        }
+    }
+}
+
+impl<K, V> Tree<K, V>
+where
+    K: Ord,
+{
+    /**
+     * Create an empty tree, which threads nodes to their successors.
+     */
+    pub fn with_threads() -> ThreadedTree<K, V> {
+        ThreadedTree::new()
+    }
+}
+
+/**
+ * In-order iterator, which follows threads from node to node.
+ */
+struct Threads<'a, K, V> {
+    nodes: &'a [Node<K, V>],
+    next: Option<usize>,
+}
+
+impl<'a, K, V> Iterator for Threads<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = &self.nodes[self.next?];
+        self.next = match node.right {
+            Right::Thread(successor) => successor,
+            Right::Child(mut index) => {
+                while let Some(left) = self.nodes[index].left {
+                    index = left;
+                }
+                Some(index)
+            }
+        };
+        Some((&node.key, &node.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Tree;
+
+    #[test]
+    fn iteration_follows_threads() {
+        let mut tree = Tree::with_threads();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i);
+        }
+        assert_eq!(tree.insert(6, 60), Some(6));
+
+        assert_eq!(tree.len(), 8);
+        assert!(tree
+            .iter()
+            .map(|(k, _)| *k)
+            .eq([1, 3, 5, 6, 11, 42, 65, 123]));
+        assert_eq!(tree.get(&6), Some(&60));
+        assert!(!tree.contains(&7));
+
+        *tree.get_mut(&42).unwrap() += 1;
+        assert_eq!(tree.get(&42), Some(&43));
+        tree.clear();
+        assert_eq!(tree.iter().next(), None);
+    }
+
+    #[test]
+    fn iteration_uses_constant_memory() {
+        // Ascending insert into an unbalanced tree is quadratic,
+        // so the long chain is shorter than the permuted batch.
+        let mut chain = Tree::with_threads();
+        for i in 0..10_000 {
+            chain.insert(i, i);
+        }
+        let iter = chain.iter();
+        assert!(core::mem::size_of_val(&iter) <= 4 * core::mem::size_of::<usize>());
+        assert!(iter.map(|(k, _)| *k).eq(0..10_000));
+
+        // Multiplication by a number coprime with the modulus permutes keys.
+        let mut tree = Tree::with_threads();
+        for i in 0..100_000u64 {
+            tree.insert(i * 7_919 % 100_003, i);
+        }
+        let iter = tree.iter();
+        assert!(core::mem::size_of_val(&iter) <= 4 * core::mem::size_of::<usize>());
+        let keys: Vec<u64> = iter.map(|(k, _)| *k).collect();
+        assert_eq!(keys.len(), 100_000);
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
@@ -0,0 +1,368 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Treap, i.e. randomized binary search tree. Each node gets a random
+//! priority, and rotations keep priorities of parents not less than
+//! priorities of their children, so the shape of the tree is the shape
+//! of a tree built from keys in random order, with expected O(log n) depth.
+
+use alloc::boxed::Box;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+
+use crate::binary_node::{self, BinaryNode, InOrder, OwnedNode};
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+/**
+ * Seed of the generator of priorities for `Treap::new`.
+ */
+const DEFAULT_SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+#[derive(Debug)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+
+    left: Link<K, V>,
+    right: Link<K, V>,
+
+    // Random priority, which is not greater than priority of the parent.
+    priority: u64,
+}
+
+impl<K, V> Node<K, V> {
+    fn new(key: K, value: V, priority: u64) -> Self {
+        Self {
+            key,
+            value,
+            left: None,
+            right: None,
+            priority,
+        }
+    }
+
+    fn priority(link: &Link<K, V>) -> Option<u64> {
+        link.as_ref().map(|node| node.priority)
+    }
+}
+
+impl<K, V> BinaryNode for Node<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn key(&self) -> &K {
+        &self.key
+    }
+
+    fn value(&self) -> &V {
+        &self.value
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+impl<K, V> OwnedNode for Node<K, V> {
+    fn value_mut(&mut self) -> &mut V {
+        &mut self.value
+    }
+
+    fn left_link(&mut self) -> &mut Link<K, V> {
+        &mut self.left
+    }
+
+    fn right_link(&mut self) -> &mut Link<K, V> {
+        &mut self.right
+    }
+}
+
+/**
+ * Randomly balanced binary search tree with the same API as `Tree`.
+ * Priorities come from a pseudo-random generator, so trees with the same
+ * seed and the same sequence of updates have the same shape.
+ */
+#[derive(Debug)]
+pub struct Treap<K, V> {
+    root: Link<K, V>,
+    len: usize,
+
+    // State of the SplitMix64 generator of priorities.
+    state: u64,
+}
+
+impl<K, V> Default for Treap<K, V> {
+    fn default() -> Self {
+        Self::with_seed(DEFAULT_SEED)
+    }
+}
+
+impl<K, V> Treap<K, V> {
+    /**
+     * Create an empty tree with the seed for priorities of its nodes.
+     */
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            root: None,
+            len: 0,
+            state: seed,
+        }
+    }
+
+    /**
+     * Iterate over references to keys and values in ascending key order.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        InOrder::new(self.root.as_deref())
+    }
+
+    /**
+     * Number of key-value pairs in the tree.
+     */
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * Number of nodes on the longest path from the root to a leaf.
+     */
+    pub fn height(&self) -> usize {
+        binary_node::height(self.root.as_deref())
+    }
+
+    /**
+     * Next priority from the SplitMix64 generator.
+     */
+    fn next_priority(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl<K, V> Treap<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Insert key-value pair into tree. When the key is already present,
+     * its value is replaced and the previous value is returned.
+     */
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let priority = self.next_priority();
+        let previous = Self::insert_at(&mut self.root, key, value, priority);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    /**
+     * Insert key-value pair into tree, only when the key is not present.
+     * Returns false and keeps the existing value otherwise.
+     */
+    pub fn try_insert(&mut self, key: K, value: V) -> bool {
+        if self.contains(&key) {
+            return false;
+        }
+        self.insert(key, value);
+        true
+    }
+
+    /**
+     * Insert the new node as a leaf, then rotate it up, while its priority
+     * is greater than priority of its parent.
+     */
+    fn insert_at(slot: &mut Link<K, V>, key: K, value: V, priority: u64) -> Option<V> {
+        let Some(current_node) = slot else {
+            *slot = Some(Box::new(Node::new(key, value, priority)));
+            return None;
+        };
+
+        let previous = match key.cmp(&current_node.key) {
+            Ordering::Equal => return Some(core::mem::replace(&mut current_node.value, value)),
+            Ordering::Less => Self::insert_at(&mut current_node.left, key, value, priority),
+            Ordering::Greater => Self::insert_at(&mut current_node.right, key, value, priority),
+        };
+
+        let node = slot.take()?;
+        *slot = Some(if Node::priority(&node.left) > Some(node.priority) {
+            Self::rotate_right(node)
+        } else if Node::priority(&node.right) > Some(node.priority) {
+            Self::rotate_left(node)
+        } else {
+            node
+        });
+        previous
+    }
+
+    /**
+     * Check is the key present in the tree.
+     */
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /**
+     * Get reference to the value stored under the key.
+     */
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        binary_node::find(self.root.as_deref(), key).map(BinaryNode::value)
+    }
+
+    /**
+     * Get mutable reference to the value stored under the key.
+     */
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        binary_node::find_mut(self.root.as_deref_mut(), key).map(OwnedNode::value_mut)
+    }
+
+    /**
+     * Remove node with the key from tree and return its value.
+     */
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut slot = &mut self.root;
+        loop {
+            match key.cmp(slot.as_ref()?.key.borrow()) {
+                Ordering::Equal => break,
+                Ordering::Less => slot = &mut slot.as_mut()?.left,
+                Ordering::Greater => slot = &mut slot.as_mut()?.right,
+            }
+        }
+
+        let removed = Self::remove_root(slot);
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /**
+     * Rotate the root of the subtree in the slot down, lifting the child
+     * of greater priority, until it has one child at most, then replace
+     * it by the child.
+     */
+    fn remove_root(mut slot: &mut Link<K, V>) -> Option<V> {
+        loop {
+            let node = slot.take()?;
+            match (Node::priority(&node.left), Node::priority(&node.right)) {
+                (Some(left), Some(right)) if left > right => {
+                    slot = &mut slot.insert(Self::rotate_right(node)).right;
+                }
+                (Some(_), Some(_)) => {
+                    slot = &mut slot.insert(Self::rotate_left(node)).left;
+                }
+                _ => {
+                    let mut node = node;
+                    *slot = node.left.take().or_else(|| node.right.take());
+                    return Some(node.value);
+                }
+            }
+        }
+    }
+
+    /**
+     * Move the right child of the node up, the node becomes its left child.
+     */
+    fn rotate_left(mut node: Box<Node<K, V>>) -> Box<Node<K, V>> {
+        let Some(mut pivot) = node.right.take() else {
+            return node;
+        };
+        node.right = pivot.left.take();
+        pivot.left = Some(node);
+        pivot
+    }
+
+    /**
+     * Move the left child of the node up, the node becomes its right child.
+     */
+    fn rotate_right(mut node: Box<Node<K, V>>) -> Box<Node<K, V>> {
+        let Some(mut pivot) = node.left.take() else {
+            return node;
+        };
+        node.left = pivot.right.take();
+        pivot.right = Some(node);
+        pivot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Check, that priorities of children are not greater than priority
+     * of their parent.
+     */
+    fn assert_heap_order<K, V>(tree: &Treap<K, V>) {
+        let mut stack: Vec<&Node<K, V>> = tree.root.as_deref().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            assert!(Node::priority(&node.left) <= Some(node.priority));
+            assert!(Node::priority(&node.right) <= Some(node.priority));
+            stack.extend(node.left.as_deref());
+            stack.extend(node.right.as_deref());
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_shape() {
+        let shape = |seed| {
+            let mut tree = Treap::with_seed(seed);
+            for i in 0..100 {
+                tree.insert(i, ());
+            }
+            let root = tree.root.as_ref().map(|node| node.key);
+            (root, tree.height())
+        };
+
+        assert_eq!(shape(7), shape(7));
+        assert_ne!(shape(7), shape(8));
+    }
+
+    #[test]
+    fn updates_keep_heap_order() {
+        let mut tree = Treap::new();
+        for i in 0..1_000 {
+            tree.insert(i, i);
+        }
+        assert_heap_order(&tree);
+
+        for i in (0..1_000).filter(|i| i % 3 != 0) {
+            tree.remove(&i);
+        }
+        assert_heap_order(&tree);
+    }
+}
@@ -0,0 +1,374 @@
+// Author: Volodymyr M. Lisivka <vlisivka@gmail.com>
+
+//! Weight-balanced tree. Each node stores the size of its subtree, and
+//! a subtree is rebuilt into a perfectly balanced one, when one of its
+//! children becomes too heavy, so the tree height stays O(log n) with
+//! amortized O(log n) updates, and sizes give order statistics for free.
+
+use alloc::boxed::Box;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+
+use crate::binary_node::{self, BinaryNode, InOrder, OwnedNode};
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+/**
+ * A subtree is rebalanced, when a child holds more than ALPHA_NUMERATOR
+ * out of ALPHA_DENOMINATOR nodes of the subtree, i.e. alpha is 0.7.
+ */
+const ALPHA_NUMERATOR: usize = 7;
+const ALPHA_DENOMINATOR: usize = 10;
+
+#[derive(Debug)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+
+    left: Link<K, V>,
+    right: Link<K, V>,
+
+    // Number of nodes in the subtree rooted at this node.
+    size: usize,
+}
+
+impl<K, V> Node<K, V> {
+    fn size(link: &Link<K, V>) -> usize {
+        link.as_ref().map_or(0, |node| node.size)
+    }
+
+    fn update_size(&mut self) {
+        self.size = 1 + Self::size(&self.left) + Self::size(&self.right);
+    }
+
+    /**
+     * Check is one of children heavier than alpha times the subtree.
+     */
+    fn is_unbalanced(&self) -> bool {
+        let heavier = Self::size(&self.left).max(Self::size(&self.right));
+        heavier * ALPHA_DENOMINATOR > self.size * ALPHA_NUMERATOR
+    }
+}
+
+impl<K, V> BinaryNode for Node<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn key(&self) -> &K {
+        &self.key
+    }
+
+    fn value(&self) -> &V {
+        &self.value
+    }
+
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+impl<K, V> OwnedNode for Node<K, V> {
+    fn value_mut(&mut self) -> &mut V {
+        &mut self.value
+    }
+
+    fn left_link(&mut self) -> &mut Link<K, V> {
+        &mut self.left
+    }
+
+    fn right_link(&mut self) -> &mut Link<K, V> {
+        &mut self.right
+    }
+
+    fn relinked(&mut self, size: usize) {
+        self.size = size;
+    }
+}
+
+/**
+ * Balanced binary search tree with the same API as `Tree`, which keeps
+ * sizes of sibling subtrees within the alpha bound.
+ */
+#[derive(Debug)]
+pub struct WeightBalancedTree<K, V> {
+    root: Link<K, V>,
+}
+
+impl<K, V> Default for WeightBalancedTree<K, V> {
+    fn default() -> Self {
+        Self { root: None }
+    }
+}
+
+impl<K, V> WeightBalancedTree<K, V> {
+    /**
+     * Iterate over references to keys and values in ascending key order.
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        InOrder::new(self.root.as_deref())
+    }
+
+    /**
+     * Number of key-value pairs in the tree.
+     */
+    pub fn len(&self) -> usize {
+        Node::size(&self.root)
+    }
+
+    /**
+     * Check is the tree empty.
+     */
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /**
+     * Number of nodes on the longest path from the root to a leaf.
+     */
+    pub fn height(&self) -> usize {
+        binary_node::height(self.root.as_deref())
+    }
+
+    /**
+     * Entry, which is n-th in sorted order, counting from zero.
+     */
+    pub fn select(&self, mut n: usize) -> Option<(&K, &V)> {
+        let mut subtree = self.root.as_deref();
+        while let Some(node) = subtree {
+            let left_size = Node::size(&node.left);
+            subtree = match n.cmp(&left_size) {
+                Ordering::Equal => return Some((&node.key, &node.value)),
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => {
+                    n -= left_size + 1;
+                    node.right.as_deref()
+                }
+            };
+        }
+        None
+    }
+
+    /**
+     * Restore the weight balance of the subtree in the slot after
+     * a change in one of its children, by rebuilding it when needed.
+     */
+    fn rebalance(slot: &mut Link<K, V>) {
+        let Some(node) = slot.as_deref_mut() else {
+            return;
+        };
+        node.update_size();
+        if node.is_unbalanced() {
+            binary_node::rebuild(slot);
+        }
+    }
+}
+
+impl<K, V> WeightBalancedTree<K, V>
+where
+    K: Ord,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Insert key-value pair into tree. When the key is already present,
+     * its value is replaced and the previous value is returned.
+     */
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        Self::insert_at(&mut self.root, key, value)
+    }
+
+    fn insert_at(slot: &mut Link<K, V>, key: K, value: V) -> Option<V> {
+        let Some(current_node) = slot else {
+            *slot = Some(Box::new(Node {
+                key,
+                value,
+                left: None,
+                right: None,
+                size: 1,
+            }));
+            return None;
+        };
+
+        let previous = match key.cmp(&current_node.key) {
+            Ordering::Equal => return Some(core::mem::replace(&mut current_node.value, value)),
+            Ordering::Less => Self::insert_at(&mut current_node.left, key, value),
+            Ordering::Greater => Self::insert_at(&mut current_node.right, key, value),
+        };
+
+        if previous.is_none() {
+            Self::rebalance(slot);
+        }
+        previous
+    }
+
+    /**
+     * Check is the key present in the tree.
+     */
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /**
+     * Get reference to the value stored under the key.
+     */
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        binary_node::find(self.root.as_deref(), key).map(BinaryNode::value)
+    }
+
+    /**
+     * Get mutable reference to the value stored under the key.
+     */
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        binary_node::find_mut(self.root.as_deref_mut(), key).map(OwnedNode::value_mut)
+    }
+
+    /**
+     * Number of keys in the tree, which are less than the given key.
+     */
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut rank = 0;
+        let mut subtree = self.root.as_deref();
+        while let Some(node) = subtree {
+            subtree = match key.cmp(node.key.borrow()) {
+                Ordering::Equal => return rank + Node::size(&node.left),
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => {
+                    rank += Node::size(&node.left) + 1;
+                    node.right.as_deref()
+                }
+            };
+        }
+        rank
+    }
+
+    /**
+     * Remove node with the key from tree and return its value.
+     */
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Self::remove_at(&mut self.root, key)
+    }
+
+    fn remove_at<Q>(slot: &mut Link<K, V>, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let current_node = slot.as_deref_mut()?;
+
+        let removed = match key.cmp(current_node.key.borrow()) {
+            Ordering::Less => Self::remove_at(&mut current_node.left, key),
+            Ordering::Greater => Self::remove_at(&mut current_node.right, key),
+            Ordering::Equal => {
+                let mut removed_node = slot.take()?;
+                *slot = match (removed_node.left.take(), removed_node.right.take()) {
+                    (None, child) | (child, None) => child,
+
+                    (Some(left), Some(right)) => {
+                        let mut right = Some(right);
+                        let mut successor = Self::remove_min(&mut right)?;
+                        successor.left = Some(left);
+                        successor.right = right;
+                        Some(successor)
+                    }
+                };
+                Some(removed_node.value)
+            }
+        };
+
+        if removed.is_some() {
+            Self::rebalance(slot);
+        }
+        removed
+    }
+
+    /**
+     * Detach the leftmost node of the subtree and rebalance the path to it.
+     */
+    fn remove_min(slot: &mut Link<K, V>) -> Option<Box<Node<K, V>>> {
+        let current_node = slot.as_deref_mut()?;
+
+        if current_node.left.is_some() {
+            let min_node = Self::remove_min(&mut current_node.left);
+            Self::rebalance(slot);
+            return min_node;
+        }
+
+        let mut min_node = slot.take()?;
+        *slot = min_node.right.take();
+        Some(min_node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Node, WeightBalancedTree};
+
+    /**
+     * Check sizes and the weight balance of every node.
+     */
+    fn assert_weight_balanced<K, V>(tree: &WeightBalancedTree<K, V>) {
+        let mut stack: Vec<&Node<K, V>> = tree.root.as_deref().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            assert_eq!(
+                node.size,
+                1 + Node::size(&node.left) + Node::size(&node.right)
+            );
+            assert!(!node.is_unbalanced(), "node is not weight-balanced");
+            stack.extend(node.left.as_deref());
+            stack.extend(node.right.as_deref());
+        }
+    }
+
+    #[test]
+    fn updates_keep_weight_balance() {
+        let mut tree = WeightBalancedTree::new();
+        for i in 0..1_000 {
+            tree.insert(i, i);
+        }
+        assert_weight_balanced(&tree);
+
+        for i in (0..1_000).filter(|i| i % 3 != 0) {
+            tree.remove(&i);
+        }
+        assert_weight_balanced(&tree);
+    }
+
+    #[test]
+    fn select_and_rank() {
+        let mut tree = WeightBalancedTree::new();
+        for i in [5, 3, 65, 123, 6, 11, 3, 1, 5, 42] {
+            tree.insert(i, i);
+        }
+
+        assert_eq!(tree.select(3), Some((&6, &6)));
+        assert_eq!(tree.rank(&6), 3);
+        assert_eq!(tree.rank(&7), 4);
+        assert_eq!(tree.select(8), None);
+    }
+}